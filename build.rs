@@ -1,6 +1,10 @@
 fn main() {
-    println!("cargo:rustc-link-arg-bins=--nmagic");
-    println!("cargo:rustc-link-arg-bins=-Tlink.x");
-    #[cfg(feature = "defmt")]
-    println!("cargo:rustc-link-arg-bins=-Tdefmt.x");
+    // 链接脚本只用于固件和板上运行的示例,主机上构建库和工具时不需要
+    #[cfg(feature = "firmware")]
+    for kind in ["bins", "examples"] {
+        println!("cargo:rustc-link-arg-{kind}=--nmagic");
+        println!("cargo:rustc-link-arg-{kind}=-Tlink.x");
+        #[cfg(feature = "defmt")]
+        println!("cargo:rustc-link-arg-{kind}=-Tdefmt.x");
+    }
 }
@@ -0,0 +1,128 @@
+//! 在STM32F103上测量协议解析和近距离滤波的CPU周期数
+//!
+//! 分别用浮点和定点角度运行,对比两者的开销:
+//!
+//! ```text
+//! cargo run --release --example bench_filter
+//! cargo run --release --example bench_filter --features fixed-point
+//! ```
+//!
+//! # 测量结果
+//!
+//! 还没有在STM32F103上运行过,下表没有实测数据。运行后按角度表示填入 `avg` 周期数,
+//! 同时注明芯片主频和编译选项:
+//!
+//! | 角度表示 | 解析一帧 | near_filter | near_filter(严格) |
+//! | -------- | -------- | ----------- | ------------------ |
+//! | f32      | 未测量   | 未测量      | 未测量             |
+//! | u16      | 未测量   | 未测量      | 未测量             |
+
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+
+use cortex_m::peripheral::DWT;
+use defmt::info;
+use embassy_executor::Spawner;
+use heapless::Vec;
+use ldlidar_driver::angle;
+use ldlidar_driver::protocol::ld06::{self, Ld06Parser, PACKET_LEN, POINT_PER_PACK};
+use ldlidar_driver::{PointData, Slbf};
+use {defmt_rtt as _, panic_probe as _};
+
+/// 每项测量的重复次数
+const ROUNDS: u32 = 10;
+
+/// 一圈的点数(LD06,10Hz)
+const SCAN_POINTS: usize = 450;
+
+/// 转速(度/秒)
+const SPEED: u16 = 3600;
+
+/// 生成一圈合成数据:大部分为远处的点,每隔一段混入近距离的中低置信度点
+fn synthetic_scan() -> Vec<PointData, SCAN_POINTS> {
+    let mut points = Vec::new();
+    for i in 0..SCAN_POINTS {
+        let near = i % 30 < 8;
+        points
+            .push(PointData {
+                angle: angle::from_centidegrees((i * 80) as u16),
+                distance: if near { 300 + (i % 7) as u16 * 40 } else { 2500 },
+                intensity: if near { 100 + (i % 5) as u8 * 25 } else { 210 },
+                timestamp: 0,
+            })
+            .ok();
+    }
+    points
+}
+
+/// 生成一个100.00度到108.80度的数据包
+fn synthetic_packet() -> [u8; PACKET_LEN] {
+    let mut frame = [0u8; PACKET_LEN];
+    frame[0] = ld06::PKG_HEADER;
+    frame[1] = ld06::PKG_VER_LEN;
+    frame[2..4].copy_from_slice(&SPEED.to_le_bytes());
+    frame[4..6].copy_from_slice(&10000u16.to_le_bytes());
+    for i in 0..POINT_PER_PACK {
+        let offset = 6 + i * 3;
+        frame[offset..offset + 2].copy_from_slice(&(500 + 10 * i as u16).to_le_bytes());
+        frame[offset + 2] = 200;
+    }
+    frame[42..44].copy_from_slice(&10880u16.to_le_bytes());
+    frame[PACKET_LEN - 1] = ld06::crc8(&frame[..PACKET_LEN - 1]);
+    frame
+}
+
+/// 运行 `f` 若干次,返回最少和平均周期数
+fn measure<R>(mut f: impl FnMut() -> R) -> (u32, u32) {
+    let mut min = u32::MAX;
+    let mut total = 0;
+    for _ in 0..ROUNDS {
+        let start = DWT::cycle_count();
+        black_box(f());
+        let cycles = DWT::cycle_count().wrapping_sub(start);
+        min = min.min(cycles);
+        total += cycles;
+    }
+    (min, total / ROUNDS)
+}
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let _p = embassy_stm32::init(Default::default());
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+
+    info!(
+        "angle representation: {}",
+        if cfg!(feature = "fixed-point") {
+            "u16 centidegrees"
+        } else {
+            "f32 degrees"
+        }
+    );
+
+    let frame = synthetic_packet();
+    let mut parser = Ld06Parser::new();
+    let (min, avg) = measure(|| parser.feed(black_box(&frame)).count());
+    info!("parse packet: min {} avg {} cycles", min, avg);
+
+    let points = synthetic_scan();
+    for strict in [false, true] {
+        let filter = Slbf::new(SPEED as f32, strict);
+        let (min, avg) = measure(|| filter.near_filter::<SCAN_POINTS>(black_box(&points)).len());
+        info!(
+            "near_filter strict={}: min {} avg {} cycles, {} cycles/point",
+            strict,
+            min,
+            avg,
+            avg / SCAN_POINTS as u32
+        );
+    }
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
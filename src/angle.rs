@@ -0,0 +1,255 @@
+//! 角度表示
+//!
+//! 默认用 `f32` 表示角度(度)。STM32F103等没有FPU的芯片上浮点运算由软件实现,
+//! 开销很大,此时可以启用 `fixed-point` 特性,改用 `u16` 表示角度(0.01度),
+//! 协议解析、扫描拼接和滤波中逐点的角度计算都只使用整数运算。
+//!
+//! 角度相关的计算应通过本模块的函数完成,使代码在两种表示下都能编译
+
+use core::cmp::Ordering;
+
+/// 角度,取值范围为 `[0, FULL_TURN)`
+#[cfg(not(feature = "fixed-point"))]
+pub type Angle = f32;
+
+/// 角度(0.01度),取值范围为 `[0, FULL_TURN)`
+#[cfg(feature = "fixed-point")]
+pub type Angle = u16;
+
+/// 一整圈
+#[cfg(not(feature = "fixed-point"))]
+pub const FULL_TURN: Angle = 360.0;
+/// 一整圈
+#[cfg(feature = "fixed-point")]
+pub const FULL_TURN: Angle = 36000;
+
+/// 0度
+#[cfg(not(feature = "fixed-point"))]
+pub const ZERO: Angle = 0.0;
+/// 0度
+#[cfg(feature = "fixed-point")]
+pub const ZERO: Angle = 0;
+
+/// 半圈
+#[cfg(not(feature = "fixed-point"))]
+pub const HALF_TURN: Angle = 180.0;
+/// 半圈
+#[cfg(feature = "fixed-point")]
+pub const HALF_TURN: Angle = 18000;
+
+/// 由以0.01度为单位的整数得到角度
+#[inline]
+pub fn from_centidegrees(centidegrees: u16) -> Angle {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        centidegrees as f32 / 100.0
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        centidegrees
+    }
+}
+
+/// 由以度为单位的浮点数得到角度,定点表示下四舍五入到0.01度,舍入到360度时回到0度
+#[inline]
+pub fn from_degrees(degrees: f32) -> Angle {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        degrees
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        ((degrees * 100.0 + 0.5) as u32 % FULL_TURN as u32) as u16
+    }
+}
+
+/// 转换为以0.01度为单位的整数,浮点表示下四舍五入
+#[inline]
+pub fn to_centidegrees(angle: Angle) -> u16 {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        (angle * 100.0 + 0.5) as u16
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        angle
+    }
+}
+
+/// 转换为以度为单位的浮点数
+#[inline]
+pub fn to_degrees(angle: Angle) -> f32 {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        angle
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        angle as f32 / 100.0
+    }
+}
+
+/// 在从 `start` 开始、跨度为 `span` 的区间上插值,返回第 `index` 个等分点的角度,
+/// 定点表示下四舍五入到0.01度
+///
+/// # Arguments
+/// * `start` - 起始角度
+/// * `span` - 角度跨度
+/// * `index` - 等分点序号
+/// * `count` - 等分数
+#[inline]
+pub fn interpolate(start: Angle, span: Angle, index: usize, count: usize) -> Angle {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        let angle = start + span / count as f32 * index as f32;
+        if angle >= FULL_TURN {
+            angle - FULL_TURN
+        } else {
+            angle
+        }
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        let offset = (span as u32 * index as u32 + count as u32 / 2) / count as u32;
+        ((start as u32 + offset) % FULL_TURN as u32) as u16
+    }
+}
+
+/// 角度是否在 `[0, FULL_TURN)` 范围内,浮点表示下NaN和无穷大均视为无效
+#[inline]
+pub fn is_valid(angle: Angle) -> bool {
+    (ZERO..FULL_TURN).contains(&angle)
+}
+
+/// 比较两个角度的大小,浮点表示下对NaN也给出确定的结果,不会panic
+#[inline]
+pub fn cmp(a: Angle, b: Angle) -> Ordering {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        a.total_cmp(&b)
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        a.cmp(&b)
+    }
+}
+
+/// 两个角度数值之差的绝对值,不考虑跨越0度
+#[inline]
+pub fn abs_diff(a: Angle, b: Angle) -> Angle {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        // `f32::abs` 在core中不可用
+        if a > b {
+            a - b
+        } else {
+            b - a
+        }
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        a.abs_diff(b)
+    }
+}
+
+/// 角度乘以整数倍,定点表示下超出 `u16` 范围时取最大值
+#[inline]
+pub fn saturating_mul(angle: Angle, factor: u16) -> Angle {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        angle * factor as f32
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        angle.saturating_mul(factor)
+    }
+}
+
+/// 两个角度之间较小的夹角,考虑跨越0度
+#[inline]
+pub fn separation(a: Angle, b: Angle) -> Angle {
+    let diff = abs_diff(a, b);
+    if diff > FULL_TURN - diff {
+        FULL_TURN - diff
+    } else {
+        diff
+    }
+}
+
+/// 正弦和余弦值
+///
+/// 用泰勒级数计算,误差小于1e-6,不依赖libm
+pub fn sin_cos(angle: Angle) -> (f32, f32) {
+    let degrees = to_degrees(angle);
+    (sin_degrees(degrees), sin_degrees(degrees + 90.0))
+}
+
+/// 以弧度表示的角度的正弦和余弦值,角度可以为负
+pub fn sin_cos_radians(radians: f32) -> (f32, f32) {
+    let degrees = radians.to_degrees();
+    (sin_degrees(degrees), sin_degrees(degrees + 90.0))
+}
+
+/// 以度为单位的正弦值
+fn sin_degrees(degrees: f32) -> f32 {
+    // 规约到 [-90, 90] 度
+    let mut d = degrees % 360.0;
+    if d >= 180.0 {
+        d -= 360.0;
+    } else if d < -180.0 {
+        d += 360.0;
+    }
+    if d > 90.0 {
+        d = 180.0 - d;
+    } else if d < -90.0 {
+        d = -180.0 - d;
+    }
+
+    let x = d * (core::f32::consts::PI / 180.0);
+    let x2 = x * x;
+    x * (1.0
+        - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0 * (1.0 - x2 / 110.0)))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_wraps() {
+        let start = from_centidegrees(35500);
+        let span = from_centidegrees(880);
+        assert!((to_degrees(interpolate(start, span, 0, 11)) - 355.0).abs() < 1e-3);
+        assert!((to_degrees(interpolate(start, span, 7, 11)) - 0.6).abs() < 1e-3);
+        assert!((to_degrees(interpolate(start, span, 11, 11)) - 3.8).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_from_degrees_rounding() {
+        assert!(is_valid(from_degrees(359.995)));
+        assert!(is_valid(from_degrees(359.999)));
+        #[cfg(feature = "fixed-point")]
+        assert_eq!((from_degrees(359.995), from_degrees(123.456)), (0, 12346));
+    }
+
+    #[test]
+    fn test_separation() {
+        let near = from_degrees(359.5);
+        let far = from_degrees(0.5);
+        assert!((to_degrees(separation(near, far)) - 1.0).abs() < 1e-3);
+        assert!((to_degrees(separation(far, near)) - 1.0).abs() < 1e-3);
+        assert!(
+            (to_degrees(separation(from_degrees(10.0), from_degrees(100.0))) - 90.0).abs() < 1e-3
+        );
+    }
+
+    #[test]
+    fn test_sin_cos() {
+        for centidegrees in (0..36000).step_by(125) {
+            let (sin, cos) = sin_cos(from_centidegrees(centidegrees));
+            let radians = (centidegrees as f64 / 100.0).to_radians();
+            assert!((sin as f64 - radians.sin()).abs() < 1e-6);
+            assert!((cos as f64 - radians.cos()).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! 命令行参数解析
+
+use std::fmt;
+use std::str::FromStr;
+
+/// 命令行错误
+#[derive(Debug)]
+pub enum CliError {
+    /// 参数或数据错误
+    Message(String),
+    /// 输入输出错误
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message(message) => f.write_str(message),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// 生成 [`CliError`]
+macro_rules! cli_error {
+    ($($arg:tt)*) => {
+        $crate::args::CliError::Message(format!($($arg)*))
+    };
+}
+pub(crate) use cli_error;
+
+/// 子命令的参数
+///
+/// `--name value` 和 `--name=value` 为带值的选项,`flags` 中列出的选项不带值,其余为位置参数
+#[derive(Debug)]
+pub struct Args {
+    /// 位置参数
+    positional: Vec<String>,
+    /// 选项名称、值以及是否已被读取
+    options: Vec<(String, Option<String>, bool)>,
+}
+
+impl Args {
+    /// 解析参数
+    ///
+    /// # Arguments
+    /// * `args` - 子命令之后的参数
+    /// * `flags` - 不带值的选项名称,不含 `--`
+    pub fn parse<I>(args: I, flags: &[&str]) -> Result<Self, CliError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            if let Some((name, value)) = option.split_once('=') {
+                options.push((name.to_owned(), Some(value.to_owned()), false));
+            } else if flags.contains(&option) {
+                options.push((option.to_owned(), None, false));
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| cli_error!("option --{option} requires a value"))?;
+                options.push((option.to_owned(), Some(value), false));
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    /// 第 `index` 个位置参数
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, CliError> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| cli_error!("missing argument <{name}>"))
+    }
+
+    /// 是否给出了不带值的选项
+    pub fn flag(&mut self, name: &str) -> bool {
+        self.take(name).is_some()
+    }
+
+    /// 带值选项的值,多次给出时以最后一次为准
+    pub fn value(&mut self, name: &str) -> Option<String> {
+        self.take(name).flatten()
+    }
+
+    /// 解析带值选项
+    pub fn parse_value<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, CliError> {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| cli_error!("invalid value for --{name}: {value}"))
+            })
+            .transpose()
+    }
+
+    /// 检查是否有多余的参数
+    ///
+    /// # Arguments
+    /// * `positional` - 允许的位置参数个数
+    pub fn finish(&self, positional: usize) -> Result<(), CliError> {
+        if let Some(arg) = self.positional.get(positional) {
+            return Err(cli_error!("unexpected argument: {arg}"));
+        }
+        match self.options.iter().find(|(_, _, used)| !used) {
+            Some((name, _, _)) => Err(cli_error!("unknown option: --{name}")),
+            None => Ok(()),
+        }
+    }
+
+    /// 标记并返回选项
+    fn take(&mut self, name: &str) -> Option<Option<String>> {
+        let mut found = None;
+        for (option, value, used) in &mut self.options {
+            if option == name {
+                *used = true;
+                found = Some(value.clone());
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        Args::parse(args.iter().map(|s| s.to_string()), &["json", "strict"])
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let mut args = parse(&["in.cap", "--model", "ld19", "--json", "--count=5", "out"]).unwrap();
+        assert_eq!(args.positional(0, "input").unwrap(), "in.cap");
+        assert_eq!(args.positional(1, "output").unwrap(), "out");
+        assert!(args.positional(2, "extra").is_err());
+        assert_eq!(args.value("model").as_deref(), Some("ld19"));
+        assert!(args.flag("json"));
+        assert!(!args.flag("strict"));
+        assert_eq!(args.parse_value::<u32>("count").unwrap(), Some(5));
+        assert!(args.finish(2).is_ok());
+        assert!(args.finish(1).is_err());
+    }
+
+    #[test]
+    fn test_reject_bad_options() {
+        assert!(parse(&["--model"]).is_err());
+        let mut args = parse(&["--count", "x", "--speed", "2"]).unwrap();
+        assert!(args.parse_value::<u32>("count").is_err());
+        assert_eq!(
+            args.finish(0).unwrap_err().to_string(),
+            "unknown option: --speed"
+        );
+    }
+}
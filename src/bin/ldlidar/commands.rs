@@ -0,0 +1,398 @@
+//! 子命令
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+
+use ldlidar_driver::capture::{
+    CaptureHeader, CaptureWriter, ReplayMode, StdClock, StdWriter, Tap, MAX_RECORD,
+};
+use ldlidar_driver::host::driver::SCAN_CAPACITY;
+use ldlidar_driver::host::serial::{self, SerialPort};
+use ldlidar_driver::protocol::ParseError;
+use ldlidar_driver::scan::{ScanAssembler, ScanEvent};
+use ldlidar_driver::timing::Timestamper;
+use ldlidar_driver::Slbf;
+
+use crate::args::{cli_error, Args, CliError};
+use crate::input::{interrupted, Input, Options, Source};
+use crate::output::{self, Format, ScanWriter};
+
+/// `dump <input> [--format text|json] [--count N]`:逐个输出解码后的数据包
+pub fn dump(mut args: Args) -> Result<(), CliError> {
+    let format = args.parse_value("format")?.unwrap_or(Format::Text);
+    if !matches!(format, Format::Text | Format::Json) {
+        return Err(cli_error!("dump only supports text and json"));
+    }
+    let count = args.parse_value::<usize>("count")?.unwrap_or(usize::MAX);
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    args.finish(1)?;
+
+    let mut input = Input::open(path, options)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut packets = 0;
+    while packets < count {
+        match input.next()? {
+            Some(Ok(packet)) => {
+                output::write_packet(&mut out, format, &packet)?;
+                packets += 1;
+            }
+            Some(Err(err)) => output::write_error(&mut out, format, &err)?,
+            None => break,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// `stats <input>`:统计转速、校验错误率和每圈点数
+pub fn stats(mut args: Args) -> Result<(), CliError> {
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    args.finish(1)?;
+
+    let mut input = Input::open(path, options)?;
+    let mut assembler = ScanAssembler::<SCAN_CAPACITY>::with_model(input.model);
+    let mut packets = 0u64;
+    let mut crc_errors = 0u64;
+    let mut other_errors = 0u64;
+    let mut speed = Range::default();
+    let mut points = Range::default();
+    let mut incomplete = 0u64;
+    while let Some(result) = input.next()? {
+        let packet = match result {
+            Ok(packet) => packet,
+            Err(ParseError::Crc { .. } | ParseError::Checksum { .. }) => {
+                crc_errors += 1;
+                continue;
+            }
+            Err(_) => {
+                other_errors += 1;
+                continue;
+            }
+        };
+        packets += 1;
+        speed.add(packet.speed as u64);
+        for point in packet.points {
+            match assembler.push(point, packet.speed) {
+                Some(ScanEvent::Complete(scan)) => points.add(scan.len() as u64),
+                Some(ScanEvent::Incomplete(_)) => incomplete += 1,
+                None => {}
+            }
+        }
+    }
+
+    let frames = packets + crc_errors + other_errors;
+    let rate = |count: u64| count as f64 * 100.0 / frames.max(1) as f64;
+    println!("model            {}", input.model.name());
+    println!("packets          {packets}");
+    println!("crc errors       {crc_errors} ({:.3}%)", rate(crc_errors));
+    println!(
+        "other errors     {other_errors} ({:.3}%)",
+        rate(other_errors)
+    );
+    println!("speed (deg/s)    {speed}");
+    println!("scan rate (Hz)   {:.2}", speed.mean() / 360.0);
+    println!(
+        "scans            {} complete, {incomplete} incomplete",
+        points.count
+    );
+    println!("points/rev       {points}");
+    Ok(())
+}
+
+/// `record <input> <output> [--firmware VERSION]`:把串口收到的原始字节录制到文件
+pub fn record(mut args: Args) -> Result<(), CliError> {
+    let firmware = args.value("firmware").unwrap_or_default();
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    let target = args.positional(1, "output")?;
+    args.finish(2)?;
+
+    let model = options.model.unwrap_or_default();
+    let source = Source::open(path, model, ReplayMode::RealTime)?;
+    let header = CaptureHeader::new(model, &firmware);
+    let file = File::create(target).map_err(|err| cli_error!("{target}: {err}"))?;
+    let writer = CaptureWriter::new(StdWriter(file), header);
+    let tap = Tap::new(source, writer, StdClock::default());
+
+    let mut input = Input::new(tap, model, options.duration);
+    let (mut packets, mut errors) = (0u64, 0u64);
+    while let Some(result) = input.next()? {
+        match result {
+            Ok(_) => packets += 1,
+            Err(_) => errors += 1,
+        }
+        if (packets + errors) % 1000 == 0 {
+            eprint!("\r{packets} packets, {errors} errors");
+        }
+    }
+    eprintln!("\r{packets} packets, {errors} errors, saved to {target}");
+    Ok(())
+}
+
+/// `replay <capture> [--speed N] [--step] [--output PATH | --pty]`:按录制时的节奏输出原始字节
+///
+/// 输出默认为标准输出,也可以是文件、串口设备或新建的伪终端
+pub fn replay(mut args: Args) -> Result<(), CliError> {
+    let step = args.flag("step");
+    let speed = args.parse_value::<u16>("speed")?.unwrap_or(1);
+    let pty = args.flag("pty");
+    let output = args.value("output");
+    let path = args.positional(0, "capture")?;
+    args.finish(1)?;
+
+    let mode = match (step, speed) {
+        (true, _) => ReplayMode::Step,
+        (false, 0 | 1) => ReplayMode::RealTime,
+        (false, speed) => ReplayMode::Accelerated(speed),
+    };
+    let mut source = Source::open(path, Default::default(), mode)?;
+    let Some(header) = source.header().cloned() else {
+        return Err(cli_error!("{path}: not a capture file"));
+    };
+    eprintln!(
+        "{}, {} baud, firmware {:?}",
+        header.model.name(),
+        header.baud_rate,
+        header.firmware()
+    );
+
+    // 自己保持打开伪终端的从设备并设为原始模式,读取方来去时不会报错,数据也不会被行规程修改
+    let (mut out, _slave): (Box<dyn Write>, _) = if pty {
+        let (master, name) = serial::open_pty()?;
+        let slave = SerialPort::open(&name, header.baud_rate, serial::DEFAULT_TIMEOUT)?;
+        eprintln!("replaying to {}", name.display());
+        (Box::new(master), Some(slave))
+    } else if let Some(output) = output {
+        (open_output(&output, header.baud_rate)?, None)
+    } else {
+        (Box::new(io::stdout().lock()), None)
+    };
+
+    let mut stdin = io::stdin().lock();
+    let mut buf = [0; MAX_RECORD];
+    let mut records = 0u64;
+    while !interrupted() {
+        let len = source.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        out.write_all(&buf[..len])?;
+        out.flush()?;
+        records += 1;
+        if step {
+            eprint!(
+                "record {records} at {:.3} s, {len} bytes [Enter] ",
+                source.time() as f64 / 1e6
+            );
+            if stdin.read_line(&mut String::new())? == 0 {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `filter <input> [--strict] [--format csv|json|pcd] [--output PATH] [--count N]`:
+/// 拼接成完整的一圈并运行近距离滤波,输出滤波后的点云
+pub fn filter(mut args: Args) -> Result<(), CliError> {
+    let strict = args.flag("strict");
+    let format = args.parse_value("format")?.unwrap_or(Format::Csv);
+    if format == Format::Text {
+        return Err(cli_error!("filter does not support text output"));
+    }
+    let output = args.value("output");
+    let count = args.parse_value::<usize>("count")?.unwrap_or(usize::MAX);
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    args.finish(1)?;
+
+    let mut input = Input::open(path, options)?;
+    let out: Box<dyn Write> = match output {
+        Some(output) => Box::new(BufWriter::new(create(&output)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    let filter = Some(Slbf::new(input.model.scan_freq() as f32 * 360.0, strict));
+    emit_scans(&mut input, ScanWriter::new(out, format)?, filter, count)
+}
+
+/// `convert <input> <output> [--format F] [--filter] [--strict] [--firmware VERSION]`:在录制文件、原始字节和点云格式之间转换
+///
+/// 输出格式由 `--format` 或扩展名决定:`.cap` 为录制文件,`.bin`/`.raw` 为原始字节,
+/// `.csv`、`.jsonl`、`.pcd` 为点云
+pub fn convert(mut args: Args) -> Result<(), CliError> {
+    let format = args.value("format");
+    let filter = args.flag("filter");
+    let strict = args.flag("strict");
+    let firmware = args.value("firmware");
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    let target = args.positional(1, "output")?;
+    args.finish(2)?;
+
+    let extension = Path::new(target)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let format = format.as_deref().or(extension).unwrap_or_default();
+    let mut input = Input::open(path, options)?;
+    let out = BufWriter::new(create(target)?);
+    match format {
+        "cap" => {
+            let header = match (input.source().header(), firmware) {
+                (Some(header), None) => header.clone(),
+                (header, firmware) => {
+                    let firmware = firmware
+                        .or(header.map(|header| header.firmware().to_owned()))
+                        .unwrap_or_default();
+                    CaptureHeader::new(input.model, &firmware)
+                }
+            };
+            let mut writer = CaptureWriter::new(StdWriter(out), header);
+            copy(input.source(), |time, data| {
+                writer.record_blocking(time, data)
+            })?;
+            writer.into_inner().0.flush()?;
+        }
+        "bin" | "raw" => {
+            let mut out = out;
+            copy(input.source(), |_, data| out.write_all(data))?;
+            out.flush()?;
+        }
+        format => {
+            let format = format.parse::<Format>()?;
+            if format == Format::Text {
+                return Err(cli_error!("convert does not support text output"));
+            }
+            let filter = filter.then(|| Slbf::new(input.model.scan_freq() as f32 * 360.0, strict));
+            emit_scans(
+                &mut input,
+                ScanWriter::new(out, format)?,
+                filter,
+                usize::MAX,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 拼接成完整的一圈,可选地运行近距离滤波后输出
+fn emit_scans<W: Write>(
+    input: &mut Input,
+    mut writer: ScanWriter<W>,
+    mut filter: Option<Slbf>,
+    count: usize,
+) -> Result<(), CliError> {
+    let mut assembler = ScanAssembler::<SCAN_CAPACITY>::with_model(input.model);
+    let mut timestamper = Timestamper::with_model(input.model);
+    if let Some(filter) = &mut filter {
+        filter
+            .set_config(input.model.slbf_config())
+            .map_err(|err| cli_error!("{err:?}"))?;
+    }
+
+    while writer.scans() < count {
+        let mut packet = match input.next()? {
+            Some(Ok(packet)) => packet,
+            Some(Err(_)) => continue,
+            None => break,
+        };
+        timestamper.stamp(&mut packet, input.source().time());
+        if let Some(filter) = &mut filter {
+            filter.update_speed(packet.speed);
+        }
+        for point in packet.points {
+            if let Some(ScanEvent::Complete(scan)) = assembler.push(point, packet.speed) {
+                if let Some(filter) = &filter {
+                    filter.near_filter_in_place(&mut scan.points);
+                }
+                writer.write(scan)?;
+            }
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// 复制数据源的原始字节,同时给出接收时刻
+fn copy<E>(
+    source: &mut Source,
+    mut write: impl FnMut(u64, &[u8]) -> Result<(), E>,
+) -> Result<(), CliError>
+where
+    CliError: From<E>,
+{
+    let mut buf = [0; MAX_RECORD];
+    while !interrupted() {
+        let len = match source.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+            Err(err) => return Err(err.into()),
+        };
+        write(source.time(), &buf[..len])?;
+    }
+    Ok(())
+}
+
+/// 创建输出文件
+fn create(path: &str) -> Result<File, CliError> {
+    File::create(path).map_err(|err| cli_error!("{path}: {err}"))
+}
+
+/// 打开回放的输出,串口设备按录制时的波特率配置
+fn open_output(path: &str, baud_rate: u32) -> Result<Box<dyn Write>, CliError> {
+    let is_device = std::fs::metadata(path).is_ok_and(|meta| meta.file_type().is_char_device());
+    let out: Box<dyn Write> = if is_device {
+        Box::new(SerialPort::open(path, baud_rate, serial::DEFAULT_TIMEOUT)?)
+    } else {
+        Box::new(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?,
+        )
+    };
+    Ok(out)
+}
+
+/// 最小值、最大值和平均值
+#[derive(Default)]
+struct Range {
+    /// 样本数
+    count: u64,
+    /// 总和
+    sum: u64,
+    /// 最小值
+    min: u64,
+    /// 最大值
+    max: u64,
+}
+
+impl Range {
+    fn add(&mut self, value: u64) {
+        if self.count == 0 {
+            (self.min, self.max) = (value, value);
+        }
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum as f64 / self.count.max(1) as f64
+    }
+}
+
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.count == 0 {
+            return write!(f, "-");
+        }
+        write!(f, "{:.1} (min {}, max {})", self.mean(), self.min, self.max)
+    }
+}
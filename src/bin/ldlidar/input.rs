@@ -0,0 +1,289 @@
+//! 数据源
+//!
+//! 输入可以是串口设备、录制文件或原始字节文件,`-` 表示标准输入
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::os::unix::fs::FileTypeExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use ldlidar_driver::capture::{CaptureHeader, Replay, ReplayMode, StdClock, MAGIC};
+use ldlidar_driver::host::serial::SerialPort;
+use ldlidar_driver::protocol::{Packet, ParseError};
+use ldlidar_driver::reader::{LidarReader, ReadError, StdReader};
+use ldlidar_driver::LidarModel;
+
+use crate::args::{cli_error, Args, CliError};
+
+/// 收到SIGINT
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// 按Ctrl-C时结束读取而不是直接退出,以便输出统计结果和写完文件
+pub fn handle_interrupt() {
+    // SAFETY: 信号处理函数只写入原子变量
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// 是否已收到SIGINT
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// 打开数据源的方式
+pub struct Options {
+    /// 指定的型号,录制文件默认使用文件头中的型号
+    pub model: Option<LidarModel>,
+    /// 录制文件的回放模式
+    pub mode: ReplayMode,
+    /// 最长读取时间
+    pub duration: Option<Duration>,
+}
+
+impl Options {
+    /// 从 `--model` 和 `--duration`(秒)读取选项,录制文件不等待直接读完
+    pub fn from_args(args: &mut Args) -> Result<Self, CliError> {
+        let model = args
+            .value("model")
+            .map(|name| {
+                name.parse::<LidarModel>()
+                    .map_err(|_| cli_error!("unknown model: {name}"))
+            })
+            .transpose()?;
+        let duration = args
+            .parse_value::<f32>("duration")?
+            .map(Duration::from_secs_f32);
+        Ok(Self {
+            model,
+            mode: ReplayMode::Step,
+            duration,
+        })
+    }
+}
+
+/// 录制文件回放
+type CaptureReplay = Replay<StdReader<Box<dyn Read + Send>>, StdClock>;
+
+/// 字节数据源
+pub enum Source {
+    /// 串口设备
+    Serial {
+        /// 串口
+        port: SerialPort,
+        /// 打开的时刻
+        opened: Instant,
+    },
+    /// 录制文件
+    Capture(Box<CaptureReplay>),
+    /// 原始字节文件或标准输入
+    Raw {
+        /// 字节流
+        io: Box<dyn Read + Send>,
+        /// 已读取的字节数
+        bytes: u64,
+        /// 波特率
+        baud_rate: u32,
+    },
+}
+
+impl Source {
+    /// 打开数据源
+    ///
+    /// # Arguments
+    /// * `model` - 串口和原始字节使用的型号,决定波特率
+    /// * `mode` - 录制文件的回放模式
+    pub fn open(path: &str, model: LidarModel, mode: ReplayMode) -> Result<Self, CliError> {
+        let raw = |io| Source::Raw {
+            io,
+            bytes: 0,
+            baud_rate: model.baud_rate(),
+        };
+        if path == "-" {
+            return Ok(raw(Box::new(io::stdin())));
+        }
+        let file = File::open(path).map_err(|err| cli_error!("{path}: {err}"))?;
+        if file.metadata()?.file_type().is_char_device() {
+            drop(file);
+            let port =
+                SerialPort::open_model(path, model).map_err(|err| cli_error!("{path}: {err}"))?;
+            return Ok(Self::Serial {
+                port,
+                opened: Instant::now(),
+            });
+        }
+
+        let mut file = BufReader::new(file);
+        let mut magic = [0; 4];
+        let len = file.read(&mut magic)?;
+        let file = Box::new(io::Cursor::new(magic[..len].to_vec()).chain(file));
+        if magic[..len] != MAGIC {
+            return Ok(raw(file));
+        }
+        let replay = Replay::new(
+            StdReader(file as Box<dyn Read + Send>),
+            mode,
+            StdClock::default(),
+        )
+        .map_err(|err| cli_error!("{path}: {err}"))?;
+        Ok(Self::Capture(Box::new(replay)))
+    }
+
+    /// 录制文件头
+    pub fn header(&self) -> Option<&CaptureHeader> {
+        match self {
+            Self::Capture(replay) => Some(replay.header()),
+            _ => None,
+        }
+    }
+
+    /// 最近读到的数据的接收时刻(微秒)
+    ///
+    /// 录制文件为录制时的时刻,串口为打开后经过的时间,原始字节按波特率推算
+    pub fn time(&self) -> u64 {
+        match self {
+            Self::Serial { opened, .. } => opened.elapsed().as_micros() as u64,
+            Self::Capture(replay) => replay.time(),
+            Self::Raw {
+                bytes, baud_rate, ..
+            } => bytes * 10_000_000 / *baud_rate as u64,
+        }
+    }
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Serial { port, .. } => port.read(buf),
+            Self::Capture(replay) => replay.read(buf),
+            Self::Raw { io, bytes, .. } => {
+                let len = io.read(buf)?;
+                *bytes += len as u64;
+                Ok(len)
+            }
+        }
+    }
+}
+
+/// [`Input::poll`] 的结果
+pub enum Next {
+    /// 数据包
+    Packet(Box<Packet>),
+    /// 解析错误
+    Error(ParseError),
+    /// 串口读取超时,暂时没有数据
+    Idle,
+    /// 数据源结束、超时或收到SIGINT
+    End,
+}
+
+/// 解析数据包的输入
+pub struct Input<R = Source> {
+    /// 雷达型号
+    pub model: LidarModel,
+    /// 截止时间
+    deadline: Option<Instant>,
+    /// 读取器
+    reader: LidarReader<StdReader<R>>,
+}
+
+impl Input {
+    /// 打开数据源,录制文件默认使用文件头中的型号
+    pub fn open(path: &str, options: Options) -> Result<Self, CliError> {
+        let source = Source::open(path, options.model.unwrap_or_default(), options.mode)?;
+        let model = options
+            .model
+            .or(source.header().map(|header| header.model))
+            .unwrap_or_default();
+        Ok(Self::new(source, model, options.duration))
+    }
+
+    /// 数据源
+    pub fn source(&mut self) -> &mut Source {
+        &mut self.reader.get_mut().0
+    }
+}
+
+impl<R: Read> Input<R> {
+    /// 从任意字节流创建输入
+    pub fn new(io: R, model: LidarModel, duration: Option<Duration>) -> Self {
+        Self {
+            model,
+            deadline: duration.map(|duration| Instant::now() + duration),
+            reader: LidarReader::with_model(StdReader(io), model),
+        }
+    }
+
+    /// 读取下一个数据包或解析错误,数据源结束、超时或收到SIGINT时返回 `None`
+    ///
+    /// 串口读取超时后继续等待
+    pub fn next(&mut self) -> Result<Option<Result<Packet, ParseError>>, CliError> {
+        loop {
+            match self.poll()? {
+                Next::Packet(packet) => return Ok(Some(Ok(*packet))),
+                Next::Error(err) => return Ok(Some(Err(err))),
+                Next::Idle => continue,
+                Next::End => return Ok(None),
+            }
+        }
+    }
+
+    /// 与 [`Input::next`] 相同,但串口读取超时时返回 [`Next::Idle`],以便调用方处理按键等事件
+    pub fn poll(&mut self) -> Result<Next, CliError> {
+        loop {
+            if interrupted()
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Ok(Next::End);
+            }
+            match self.reader.read_packet_blocking() {
+                Ok(packet) => return Ok(Next::Packet(Box::new(packet))),
+                Err(ReadError::Parse(err)) => return Ok(Next::Error(err)),
+                Err(ReadError::Eof) => return Ok(Next::End),
+                Err(ReadError::Io(err)) if err.kind() == io::ErrorKind::TimedOut => {
+                    return Ok(Next::Idle)
+                }
+                Err(ReadError::Io(err)) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(ReadError::Io(err)) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 第一次读取超时,之后结束的数据源
+    struct Silent(bool);
+
+    impl Read for Silent {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            if std::mem::replace(&mut self.0, true) {
+                Ok(0)
+            } else {
+                Err(io::ErrorKind::TimedOut.into())
+            }
+        }
+    }
+
+    #[test]
+    fn test_poll_returns_on_timeout() {
+        let mut input = Input::new(Silent(false), LidarModel::Ld06, None);
+        assert!(matches!(input.poll(), Ok(Next::Idle)));
+        assert!(matches!(input.poll(), Ok(Next::End)));
+
+        let mut input = Input::new(Silent(false), LidarModel::Ld06, None);
+        assert!(matches!(input.next(), Ok(None)));
+    }
+}
@@ -0,0 +1,96 @@
+//! 主机端命令行工具
+//!
+//! 不需要编写代码即可检查雷达:解码数据包、统计错误率、录制和回放原始数据、运行近距离滤波、
+//! 转换录制文件和点云格式,以及在终端中实时查看扫描。
+//!
+//! ```text
+//! cargo ldlidar stats /dev/ttyUSB0 --duration 10
+//! cargo ldlidar record /dev/ttyUSB0 field.cap --firmware 2.1.3
+//! cargo ldlidar filter field.cap --format pcd --output field.pcd
+//! cargo ldlidar view /dev/ttyUSB0 --range 3
+//! ```
+//!
+//! 默认特性编译的是MCU固件,默认目标也是 `thumbv7m-none-eabi`,
+//! 所以 `cargo run --features cli --bin ldlidar` 无法编译。`cargo ldlidar` 是
+//! `.cargo/config.toml` 中的别名,展开为
+//! `cargo run --no-default-features --features cli --target x86_64-unknown-linux-gnu --bin ldlidar --`
+
+mod args;
+mod commands;
+mod input;
+mod output;
+mod view;
+
+use std::io;
+use std::process::ExitCode;
+
+use args::{Args, CliError};
+
+const USAGE: &str = "\
+usage: ldlidar <command> [options]
+
+commands:
+  dump <input> [--format text|json] [--count N]
+      decode packets, one per line
+  stats <input>
+      speed, CRC error rate and points per revolution
+  record <input> <output.cap> [--firmware VERSION]
+      record raw bytes with receive timestamps
+  replay <capture> [--speed N] [--step] [--output PATH | --pty]
+      replay raw bytes in real time, N times faster, or one record per Enter
+  filter <input> [--strict] [--format csv|json|pcd] [--output PATH] [--count N]
+      assemble scans and run the near-range filter
+  convert <input> <output> [--format cap|raw|csv|json|pcd] [--filter] [--strict]
+          [--firmware VERSION]
+      convert between capture, raw bytes and point-cloud formats
+  view <input> [--strict] [--range M]
+      live polar plot in the terminal; removed points are red
+      keys: +/- zoom, space pause, s toggle strict policy, q quit
+
+<input> is a serial device, a capture file, a raw byte dump, or - for stdin.
+
+common options:
+  --model NAME       lidar model (default: from the capture header, or LD06)
+  --duration SECS    stop reading after SECS seconds
+
+build and run with the `cargo ldlidar <command>` alias. The default features
+build the firmware for the MCU, so a plain `cargo run --features cli` fails; use
+  cargo run --no-default-features --features cli \\
+      --target x86_64-unknown-linux-gnu --bin ldlidar -- <command>
+";
+
+fn main() -> ExitCode {
+    let mut argv = std::env::args().skip(1);
+    let Some(command) = argv.next() else {
+        eprint!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    if matches!(command.as_str(), "-h" | "--help" | "help") {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let flags = ["pty", "step", "strict", "filter"];
+    let result = Args::parse(argv, &flags).and_then(|args| {
+        input::handle_interrupt();
+        match command.as_str() {
+            "dump" => commands::dump(args),
+            "stats" => commands::stats(args),
+            "record" => commands::record(args),
+            "replay" => commands::replay(args),
+            "filter" => commands::filter(args),
+            "convert" => commands::convert(args),
+            "view" => view::view(args),
+            _ => Err(args::cli_error!("unknown command: {command}\n\n{USAGE}")),
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // 输出被管道另一端提前关闭,例如 `| head`
+        Err(CliError::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("ldlidar: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! 输出格式
+//!
+//! 数据包可以输出为文本或JSON行,完整的一圈可以输出为CSV、JSON行或PCD点云
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use ldlidar_driver::angle;
+use ldlidar_driver::host::HostScan;
+use ldlidar_driver::protocol::{Packet, ParseError};
+
+use crate::args::{cli_error, CliError};
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// 便于阅读的文本
+    Text,
+    /// 每行一个JSON对象
+    Json,
+    /// CSV,每行一个点
+    Csv,
+    /// PCL的ASCII点云文件
+    Pcd,
+}
+
+impl FromStr for Format {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" | "txt" => Ok(Self::Text),
+            "json" | "jsonl" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "pcd" => Ok(Self::Pcd),
+            _ => Err(cli_error!("unknown format: {s}")),
+        }
+    }
+}
+
+/// 按 `format` 输出一个数据包,只支持文本和JSON
+pub fn write_packet(out: &mut impl Write, format: Format, packet: &Packet) -> io::Result<()> {
+    let start = angle::to_degrees(packet.start_angle);
+    let end = angle::to_degrees(packet.end_angle);
+    let mut line = String::new();
+    if format == Format::Json {
+        let _ = write!(
+            line,
+            "{{\"timestamp\":{},\"speed\":{},\"start_angle\":{start:.2},\"end_angle\":{end:.2},\"points\":[",
+            packet.timestamp, packet.speed
+        );
+        for (i, point) in packet.points.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let _ = write!(
+                line,
+                "{separator}{{\"angle\":{:.2},\"distance\":{},\"intensity\":{}}}",
+                angle::to_degrees(point.angle),
+                point.distance,
+                point.intensity
+            );
+        }
+        line.push_str("]}");
+    } else {
+        let _ = write!(
+            line,
+            "ts={:5} speed={:4} angle={start:6.2}..{end:6.2} points={}:",
+            packet.timestamp,
+            packet.speed,
+            packet.points.len()
+        );
+        for point in &packet.points {
+            let _ = write!(line, " {}/{}", point.distance, point.intensity);
+        }
+    }
+    writeln!(out, "{line}")
+}
+
+/// 按 `format` 输出一个解析错误
+pub fn write_error(out: &mut impl Write, format: Format, err: &ParseError) -> io::Result<()> {
+    match format {
+        Format::Json => writeln!(out, "{{\"error\":\"{err:?}\"}}"),
+        _ => writeln!(out, "error: {err:?}"),
+    }
+}
+
+/// 逐圈输出点云
+pub struct ScanWriter<W: Write> {
+    /// 输出
+    out: W,
+    /// 格式
+    format: Format,
+    /// 已输出的圈数
+    scans: usize,
+    /// PCD需要在文件头中给出点数,先缓存所有点:x、y(m)、强度和圈号
+    cloud: Vec<(f32, f32, u8, usize)>,
+}
+
+impl<W: Write> ScanWriter<W> {
+    /// 创建输出,CSV格式立即写入表头,不支持文本格式
+    pub fn new(mut out: W, format: Format) -> io::Result<Self> {
+        if format == Format::Text {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "scans cannot be written as text",
+            ));
+        }
+        if format == Format::Csv {
+            writeln!(out, "scan,timestamp,angle,distance,intensity,x,y")?;
+        }
+        Ok(Self {
+            out,
+            format,
+            scans: 0,
+            cloud: Vec::new(),
+        })
+    }
+
+    /// 已输出的圈数
+    pub fn scans(&self) -> usize {
+        self.scans
+    }
+
+    /// 输出一圈
+    pub fn write(&mut self, scan: &HostScan) -> io::Result<()> {
+        let index = self.scans;
+        self.scans += 1;
+        match self.format {
+            Format::Csv => {
+                for point in &scan.points {
+                    let (x, y) = cartesian(point.angle, point.distance);
+                    writeln!(
+                        self.out,
+                        "{index},{},{:.2},{},{},{x:.4},{y:.4}",
+                        point.timestamp,
+                        angle::to_degrees(point.angle),
+                        point.distance,
+                        point.intensity
+                    )?;
+                }
+            }
+            Format::Pcd => {
+                let points = scan.points.iter().filter(|point| point.distance != 0);
+                self.cloud.extend(points.map(|point| {
+                    let (x, y) = cartesian(point.angle, point.distance);
+                    (x, y, point.intensity, index)
+                }));
+            }
+            Format::Json => {
+                let mut line = format!(
+                    "{{\"scan\":{index},\"start_timestamp\":{},\"end_timestamp\":{},\"speed\":{},\"points\":[",
+                    scan.start_timestamp, scan.end_timestamp, scan.speed
+                );
+                for (i, point) in scan.points.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    let _ = write!(
+                        line,
+                        "{separator}[{:.2},{},{}]",
+                        angle::to_degrees(point.angle),
+                        point.distance,
+                        point.intensity
+                    );
+                }
+                line.push_str("]}");
+                writeln!(self.out, "{line}")?;
+            }
+            Format::Text => unreachable!("rejected by ScanWriter::new"),
+        }
+        Ok(())
+    }
+
+    /// 写完剩余数据并刷新输出
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.format == Format::Pcd {
+            let count = self.cloud.len();
+            write!(
+                self.out,
+                "# .PCD v0.7 - Point Cloud Data file format\n\
+                 VERSION 0.7\n\
+                 FIELDS x y z intensity scan\n\
+                 SIZE 4 4 4 4 4\n\
+                 TYPE F F F F U\n\
+                 COUNT 1 1 1 1 1\n\
+                 WIDTH {count}\n\
+                 HEIGHT 1\n\
+                 VIEWPOINT 0 0 0 1 0 0 0\n\
+                 POINTS {count}\n\
+                 DATA ascii\n"
+            )?;
+            for (x, y, intensity, scan) in &self.cloud {
+                writeln!(self.out, "{x:.4} {y:.4} 0 {intensity} {scan}")?;
+            }
+        }
+        self.out.flush()
+    }
+}
+
+/// 极坐标转换为直角坐标(m)
+fn cartesian(angle: ldlidar_driver::Angle, distance: u16) -> (f32, f32) {
+    let (sin, cos) = angle::to_degrees(angle).to_radians().sin_cos();
+    let distance = distance as f32 / 1000.0;
+    (distance * cos, distance * sin)
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+    use ldlidar_driver::PointData;
+
+    use super::*;
+
+    fn point(degrees: f32, distance: u16) -> PointData {
+        PointData {
+            angle: angle::from_degrees(degrees),
+            distance,
+            intensity: 200,
+            timestamp: 7,
+        }
+    }
+
+    #[test]
+    fn test_packet_json() {
+        let packet = Packet {
+            speed: 3600,
+            start_angle: angle::from_degrees(90.0),
+            end_angle: angle::from_degrees(90.8),
+            timestamp: 123,
+            points: Vec::from_slice(&[point(90.0, 1500)]).unwrap(),
+        };
+        let mut out = std::vec::Vec::new();
+        write_packet(&mut out, Format::Json, &packet).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"timestamp\":123,\"speed\":3600,\"start_angle\":90.00,\"end_angle\":90.80,\
+             \"points\":[{\"angle\":90.00,\"distance\":1500,\"intensity\":200}]}\n"
+        );
+    }
+
+    #[test]
+    fn test_scan_csv_and_pcd() {
+        let mut scan = HostScan::default();
+        scan.points.extend([point(0.0, 1000), point(90.0, 0)]);
+
+        let mut csv = std::vec::Vec::new();
+        let mut writer = ScanWriter::new(&mut csv, Format::Csv).unwrap();
+        writer.write(&scan).unwrap();
+        writer.finish().unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("0,7,0.00,1000,200,1.0000,0.0000"));
+        assert_eq!(csv.lines().count(), 3);
+
+        let mut pcd = std::vec::Vec::new();
+        let mut writer = ScanWriter::new(&mut pcd, Format::Pcd).unwrap();
+        writer.write(&scan).unwrap();
+        writer.write(&scan).unwrap();
+        writer.finish().unwrap();
+        let pcd = String::from_utf8(pcd).unwrap();
+        assert!(pcd.contains("POINTS 2\n"));
+        assert!(pcd.ends_with("1.0000 0.0000 0 200 1\n"));
+
+        assert!(ScanWriter::new(std::vec::Vec::new(), Format::Text).is_err());
+    }
+}
@@ -0,0 +1,507 @@
+//! 终端实时极坐标视图
+//!
+//! 用盲文字符把最新一圈画成极坐标图,每个字符2×4个点。保留的点按强度着色,
+//! 被近距离滤波丢弃的点用红色标出,雷达位置用白色标出,方便在只有SSH的现场调试。
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::{Duration, Instant};
+
+use ldlidar_driver::host::driver::SCAN_CAPACITY;
+use ldlidar_driver::host::HostScan;
+use ldlidar_driver::scan::{ScanAssembler, ScanEvent};
+use ldlidar_driver::{angle, PointData, Slbf};
+
+use crate::args::{cli_error, Args, CliError};
+use crate::input::{interrupted, Input, Next, Options};
+
+/// 两次重绘的最短间隔
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 每次缩放的倍数
+const ZOOM_STEP: f32 = 1.25;
+
+/// 按强度从低到高的256色调色板:蓝、青、绿、黄
+const PALETTE: [u8; 16] = [
+    21, 27, 33, 39, 45, 51, 50, 49, 48, 47, 46, 82, 118, 154, 190, 226,
+];
+
+/// 被丢弃的点的颜色
+const REMOVED_COLOR: u8 = 196;
+
+/// 距离环的颜色
+const RING_COLOR: u8 = 238;
+
+/// 雷达位置的颜色
+const ORIGIN_COLOR: u8 = 255;
+
+/// 盲文点阵中每个点对应的位,按 `[行][列]` 索引
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// 显示实时极坐标视图
+///
+/// 按键:`+`/`-` 缩放,空格暂停,`s` 切换严格策略,`q` 退出
+pub fn view(mut args: Args) -> Result<(), CliError> {
+    let strict = args.flag("strict");
+    let range = args.parse_value::<f32>("range")?.unwrap_or(5.0);
+    let options = Options::from_args(&mut args)?;
+    let path = args.positional(0, "input")?;
+    args.finish(1)?;
+    if range.is_nan() || range <= 0.0 {
+        return Err(cli_error!("invalid value for --range: {range}"));
+    }
+
+    let mut input = Input::open(path, options)?;
+    let mut filter = Slbf::new(input.model.scan_freq() as f32 * 360.0, strict);
+    filter
+        .set_config(input.model.slbf_config())
+        .map_err(|err| cli_error!("{err:?}"))?;
+    let mut assembler = ScanAssembler::<SCAN_CAPACITY>::with_model(input.model);
+    let max_range = input.model.max_range() as f32 / 1000.0;
+    let mut state = State {
+        range: range.min(max_range),
+        strict,
+        paused: false,
+    };
+
+    let mut terminal = Terminal::open()?;
+    let mut scan: Option<Box<HostScan>> = None;
+    let mut ended = false;
+    let mut dirty = true;
+    let mut last_frame = Instant::now() - FRAME_INTERVAL;
+    // 录制文件和原始字节按接收时刻回放,串口数据本身就是实时的
+    let started = Instant::now();
+    let mut first_time = None;
+
+    loop {
+        while let Some(key) = terminal.key()? {
+            match key {
+                b'q' | b'Q' => return Ok(()),
+                b'+' | b'=' => state.range = (state.range / ZOOM_STEP).max(0.1),
+                b'-' | b'_' => state.range = (state.range * ZOOM_STEP).min(max_range),
+                b' ' | b'p' => state.paused = !state.paused,
+                b's' => {
+                    state.strict = !state.strict;
+                    filter.set_strict_policy(state.strict);
+                }
+                _ => continue,
+            }
+            dirty = true;
+        }
+        if interrupted() {
+            return Ok(());
+        }
+
+        if ended {
+            std::thread::sleep(FRAME_INTERVAL);
+        } else {
+            // 串口没有数据时也要回到循环开头处理按键
+            match input.poll()? {
+                Next::Packet(packet) => {
+                    filter.update_speed(packet.speed);
+                    for point in packet.points {
+                        if let Some(ScanEvent::Complete(complete)) =
+                            assembler.push(point, packet.speed)
+                        {
+                            if !state.paused {
+                                scan = Some(Box::new(complete.clone()));
+                                dirty = true;
+                            }
+                        }
+                    }
+                }
+                Next::Error(_) | Next::Idle => {}
+                Next::End => ended = true,
+            }
+            let time = input.source().time();
+            let elapsed = Duration::from_micros(time - *first_time.get_or_insert(time));
+            if let Some(wait) = elapsed.checked_sub(started.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+
+        if dirty && last_frame.elapsed() >= FRAME_INTERVAL {
+            let (width, height) = terminal.size();
+            let frame = render(
+                scan.as_deref(),
+                &filter,
+                &state,
+                input.model.name(),
+                width,
+                height,
+            );
+            terminal.draw(&frame)?;
+            dirty = false;
+            last_frame = Instant::now();
+        }
+    }
+}
+
+/// 视图状态
+struct State {
+    /// 画布边缘对应的距离(m)
+    range: f32,
+    /// 是否启用严格策略
+    strict: bool,
+    /// 是否暂停刷新
+    paused: bool,
+}
+
+/// 画布上一个点的颜色,取值大的覆盖取值小的
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Ink {
+    /// 距离环
+    Ring,
+    /// 雷达位置,低于所有点,近处被丢弃的点落在同一字符内时仍显示为红色
+    Origin,
+    /// 保留的点及其强度
+    Kept(u8),
+    /// 被滤波丢弃的点
+    Removed,
+}
+
+impl Ink {
+    /// 256色编号
+    fn color(self) -> u8 {
+        match self {
+            Self::Ring => RING_COLOR,
+            Self::Origin => ORIGIN_COLOR,
+            Self::Kept(intensity) => PALETTE[intensity as usize * PALETTE.len() / 256],
+            Self::Removed => REMOVED_COLOR,
+        }
+    }
+}
+
+/// 盲文点阵画布
+struct Canvas {
+    /// 宽度(字符)
+    width: usize,
+    /// 高度(字符)
+    height: usize,
+    /// 每个字符的点阵和颜色
+    cells: Vec<(u8, Option<Ink>)>,
+}
+
+impl Canvas {
+    /// 创建空白画布,点阵大小为 `2 * width` × `4 * height`
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![(0, None); width * height],
+        }
+    }
+
+    /// 点亮一个点,同一字符内的颜色取最大的 [`Ink`]
+    fn plot(&mut self, x: i32, y: i32, ink: Ink) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.width * 2 || y >= self.height * 4 {
+            return;
+        }
+        let cell = &mut self.cells[y / 4 * self.width + x / 2];
+        cell.0 |= DOTS[y % 4][x % 2];
+        cell.1 = cell.1.max(Some(ink));
+    }
+
+    /// 输出为带颜色的文本,每行一个元素
+    fn lines(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.width.max(1))
+            .map(|row| {
+                let mut line = String::new();
+                let mut current = None;
+                for &(dots, ink) in row {
+                    let Some(ink) = ink.filter(|_| dots != 0) else {
+                        line.push(' ');
+                        continue;
+                    };
+                    if current != Some(ink.color()) {
+                        current = Some(ink.color());
+                        line.push_str(&format!("\x1b[38;5;{}m", ink.color()));
+                    }
+                    line.push(char::from_u32(0x2800 + dots as u32).unwrap_or(' '));
+                }
+                if current.is_some() {
+                    line.push_str("\x1b[0m");
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+/// 画出一圈和状态栏
+///
+/// 雷达位于画布中心,0度朝右,90度朝上,画布较短一边的一半对应 `state.range`
+fn render(
+    scan: Option<&HostScan>,
+    filter: &Slbf,
+    state: &State,
+    model: &str,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let rows = height.saturating_sub(1);
+    let mut canvas = Canvas::new(width, rows);
+    let (cx, cy) = (width as f32, rows as f32 * 2.0);
+    let scale = cx.min(cy) / state.range;
+
+    // 每个距离环的间隔取1、2、5的倍数,使画布内有2到5个环
+    let ring = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0]
+        .into_iter()
+        .find(|step| state.range / step <= 5.0)
+        .unwrap_or(50.0);
+    let mut radius = ring;
+    while radius <= state.range * 1.5 {
+        let dots = (radius * scale * 8.0) as usize + 8;
+        for i in 0..dots {
+            let (sin, cos) = (i as f32 / dots as f32 * std::f32::consts::TAU).sin_cos();
+            let (x, y) = (cx + radius * scale * cos, cy - radius * scale * sin);
+            canvas.plot(x as i32, y as i32, Ink::Ring);
+        }
+        radius += ring;
+    }
+    canvas.plot(cx as i32, cy as i32, Ink::Origin);
+
+    let (mut kept, mut removed) = (0, 0);
+    if let Some(scan) = scan {
+        for (point, keep) in classify(scan, filter) {
+            if point.distance == 0 {
+                continue;
+            }
+            let ink = if keep {
+                kept += 1;
+                Ink::Kept(point.intensity)
+            } else {
+                removed += 1;
+                Ink::Removed
+            };
+            let (sin, cos) = angle::to_degrees(point.angle).to_radians().sin_cos();
+            let distance = point.distance as f32 / 1000.0 * scale;
+            canvas.plot(
+                (cx + distance * cos) as i32,
+                (cy - distance * sin) as i32,
+                ink,
+            );
+        }
+    }
+
+    let mut lines = canvas.lines();
+    let speed = scan.map_or(0.0, |scan| scan.speed as f32 / 360.0);
+    let status = format!(
+        "{model} {speed:.1} Hz | {kept} kept, {removed} removed | range {:.1} m, ring {ring} m | strict {} {}| [+/-] zoom [space] pause [s] strict [q] quit",
+        state.range,
+        if state.strict { "on" } else { "off" },
+        if state.paused { "| PAUSED " } else { "" },
+    );
+    lines.push(status.chars().take(width).collect());
+    lines
+}
+
+/// 对一圈运行近距离滤波,返回每个点以及是否被保留
+///
+/// 滤波会删除并重新排序点,所以先用时间戳字段记下每个点的下标
+fn classify(scan: &HostScan, filter: &Slbf) -> Vec<(PointData, bool)> {
+    let mut points = scan.points.clone();
+    for (i, point) in points.iter_mut().enumerate() {
+        point.timestamp = i as u64;
+    }
+    filter.near_filter_in_place(&mut points);
+    let mut keep = vec![false; scan.points.len()];
+    for point in &points {
+        keep[point.timestamp as usize] = true;
+    }
+    scan.points.iter().cloned().zip(keep).collect()
+}
+
+/// 交互终端
+///
+/// 按键从 `/dev/tty` 读取,因此数据源也可以是标准输入;退出时恢复终端设置
+struct Terminal {
+    /// 控制终端
+    tty: File,
+    /// 原来的终端设置
+    original: libc::termios,
+}
+
+impl Terminal {
+    /// 关闭回显和行缓冲,切换到备用屏幕并隐藏光标
+    fn open() -> Result<Self, CliError> {
+        let tty = File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|err| cli_error!("view needs a terminal: {err}"))?;
+        let fd = tty.as_raw_fd();
+        // SAFETY: termios是普通的C结构体,全0是合法的初始值,随后由tcgetattr填充
+        let original = unsafe {
+            let mut tio: libc::termios = core::mem::zeroed();
+            check(libc::tcgetattr(fd, &mut tio))?;
+            let original = tio;
+            // 保留ISIG,Ctrl-C仍然通过SIGINT退出
+            tio.c_lflag &= !(libc::ICANON | libc::ECHO);
+            tio.c_cc[libc::VMIN] = 0;
+            tio.c_cc[libc::VTIME] = 0;
+            check(libc::tcsetattr(fd, libc::TCSANOW, &tio))?;
+            original
+        };
+        let mut terminal = Self { tty, original };
+        terminal.tty.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        Ok(terminal)
+    }
+
+    /// 终端大小(列, 行),无法获取时为80×24
+    fn size(&self) -> (usize, usize) {
+        // SAFETY: winsize是普通的C结构体,TIOCGWINSZ只写入该结构体
+        let size = unsafe {
+            let mut size: libc::winsize = core::mem::zeroed();
+            libc::ioctl(self.fd(), libc::TIOCGWINSZ, &mut size);
+            size
+        };
+        match (size.ws_col, size.ws_row) {
+            (0, _) | (_, 0) => (80, 24),
+            (cols, rows) => (cols as usize, rows as usize),
+        }
+    }
+
+    /// 读取一个按键,没有按键时立即返回 `None`
+    fn key(&mut self) -> io::Result<Option<u8>> {
+        next_key(|| {
+            let mut byte = [0];
+            match self.tty.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => Ok(None),
+                Err(err) => Err(err),
+            }
+        })
+    }
+
+    /// 从左上角开始重绘整个屏幕
+    fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let mut frame = String::from("\x1b[H");
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                frame.push_str("\r\n");
+            }
+            frame.push_str(line);
+            frame.push_str("\x1b[K");
+        }
+        frame.push_str("\x1b[J");
+        self.tty.write_all(frame.as_bytes())
+    }
+
+    fn fd(&self) -> RawFd {
+        self.tty.as_raw_fd()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.tty.write_all(b"\x1b[?25h\x1b[?1049l");
+        // SAFETY: 恢复打开时保存的设置
+        unsafe {
+            libc::tcsetattr(self.fd(), libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// 从终端输入中取出下一个按键
+///
+/// 方向键、功能键等以ESC开头的转义序列整个跳过,避免序列中的字母被当作按键,
+/// 例如F2是 `ESC O Q`。CSI序列(`ESC [`)读到结束字节为止,其他序列只跳过ESC后的一个字节
+fn next_key(mut byte: impl FnMut() -> io::Result<Option<u8>>) -> io::Result<Option<u8>> {
+    loop {
+        match byte()? {
+            Some(0x1b) => match byte()? {
+                Some(b'[') => while let Some(0x20..=0x3F) = byte()? {},
+                Some(b'O') => {
+                    byte()?;
+                }
+                _ => {}
+            },
+            key => return Ok(key),
+        }
+    }
+}
+
+fn check(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(degrees: f32, distance: u16, intensity: u8) -> PointData {
+        PointData {
+            angle: angle::from_degrees(degrees),
+            distance,
+            intensity,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_canvas_braille() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.plot(0, 0, Ink::Ring);
+        canvas.plot(1, 3, Ink::Kept(255));
+        canvas.plot(4, 0, Ink::Removed);
+        canvas.plot(-1, 0, Ink::Removed);
+        assert_eq!(canvas.lines(), ["\x1b[38;5;226m\u{2881} \x1b[0m"]);
+        assert!(Ink::Removed > Ink::Kept(255) && Ink::Kept(0) > Ink::Origin);
+        assert!(Ink::Origin > Ink::Ring);
+        assert_eq!(Ink::Kept(0).color(), PALETTE[0]);
+    }
+
+    #[test]
+    fn test_escape_sequences_are_skipped() {
+        let keys = |input: &[u8]| {
+            let mut bytes = input.iter().copied();
+            std::iter::from_fn(|| next_key(|| Ok(bytes.next())).unwrap()).collect::<Vec<_>>()
+        };
+        // 上、右、F2、Ctrl+右、Delete、单独的ESC
+        assert_eq!(keys(b"\x1b[A+\x1b[C\x1bOQs\x1b[1;5C\x1b[3~-\x1b"), b"+s-");
+        assert_eq!(keys(b"q"), b"q");
+    }
+
+    #[test]
+    fn test_render_marks_removed_points() {
+        let mut scan = HostScan {
+            speed: 3600,
+            ..Default::default()
+        };
+        // 近处的低强度点会被丢弃
+        scan.points
+            .extend([point(0.0, 2000, 200), point(180.0, 100, 10)]);
+        let filter = Slbf::new(3600.0, false);
+        let classified = classify(&scan, &filter);
+        assert!(classified[0].1 && !classified[1].1);
+
+        let state = State {
+            range: 4.0,
+            strict: false,
+            paused: true,
+        };
+        let lines = render(Some(&scan), &filter, &state, "LD06", 120, 21);
+        assert_eq!(lines.len(), 21);
+        // 画布20行,中心在第10行,2 m处的点位于中心右侧半径的一半处
+        let kept = format!("\x1b[38;5;{}m", Ink::Kept(200).color());
+        assert!(lines[10].contains(&kept));
+        // 100 mm处被丢弃的点与雷达位置相邻,颜色不同
+        let origin = format!("\x1b[38;5;{ORIGIN_COLOR}m");
+        let removed = format!("\x1b[38;5;{REMOVED_COLOR}m");
+        assert!(lines[10].contains(&origin) && lines[10].contains(&removed));
+        assert!(lines[20].starts_with("LD06 10.0 Hz | 1 kept, 1 removed | range 4.0 m"));
+        assert!(lines[20].contains("PAUSED"));
+    }
+}
@@ -0,0 +1,339 @@
+//! 设备时钟同步
+//!
+//! LD06等型号的数据包携带一个以毫秒为单位、周期性回绕的设备时间戳。[`ClockSync`]
+//! 将其展开为单调递增的64位设备时间,并估计设备时钟与MCU(或主机)时钟之间的偏移和漂移。
+//!
+//! 接收时刻等于设备时刻加上一个非负的传输延迟,延迟最小的那些数据包最能反映两个时钟的
+//! 真实关系。因此按设备时间把数据包分组,每组只保留接收时刻与设备时刻之差最小的一个,
+//! 再对最近若干组的最小值做线性回归,得到偏移和漂移。回归只在每组结束时进行一次,
+//! 每个数据包的换算只用整数运算,Cortex-M3没有FPU,浮点运算都由软件实现。
+//!
+//! 接收间隔与设备时间的增量相差超过阈值时,认为雷达重新上电或时间戳发生了跳变,
+//! 此时丢弃历史数据重新估计,展开后的设备时间按接收间隔继续递增,保持单调
+
+use heapless::Deque;
+
+/// LD06/LD19设备时间戳的回绕周期(毫秒)
+pub const DEFAULT_PERIOD_MS: u32 = 30000;
+
+/// 参与回归的最多分组数
+pub const MAX_BUCKETS: usize = 16;
+
+/// 时钟同步配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClockSyncConfig {
+    /// 设备时间戳的回绕周期(毫秒),15位计数器为32768
+    pub period_ms: u32,
+    /// 每组覆盖的设备时间(微秒)
+    pub bucket: u64,
+    /// 接收间隔与设备时间增量之差超过该值(微秒)时视为时间戳跳变
+    pub jump_threshold: u64,
+}
+
+impl Default for ClockSyncConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// 时钟同步配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockSyncConfigError {
+    /// 回绕周期为0或超过u16的范围
+    Period,
+    /// 分组时长为0
+    Bucket,
+    /// 跳变阈值为0或不小于回绕周期的一半,无法区分跳变和回绕
+    JumpThreshold,
+}
+
+impl ClockSyncConfig {
+    /// 默认配置
+    pub const DEFAULT: Self = Self {
+        period_ms: DEFAULT_PERIOD_MS,
+        bucket: 1_000_000,
+        jump_threshold: 100_000,
+    };
+
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), ClockSyncConfigError> {
+        if self.period_ms == 0 || self.period_ms > u16::MAX as u32 + 1 {
+            return Err(ClockSyncConfigError::Period);
+        }
+        if self.bucket == 0 {
+            return Err(ClockSyncConfigError::Bucket);
+        }
+        if self.jump_threshold == 0 || self.jump_threshold >= self.period_ms as u64 * 500 {
+            return Err(ClockSyncConfigError::JumpThreshold);
+        }
+        Ok(())
+    }
+}
+
+/// 设备时刻与接收时刻之差
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// 展开后的设备时间(微秒)
+    device: u64,
+    /// 接收时刻减设备时间(微秒)
+    offset: i64,
+}
+
+/// 回归得到的时钟关系
+#[derive(Debug, Clone, Copy)]
+struct Fit {
+    /// 参考设备时间(微秒)
+    reference: u64,
+    /// 参考时刻的偏移(微秒)
+    offset: i64,
+    /// 漂移(十亿分之一)
+    drift_ppb: i64,
+}
+
+/// 设备时钟同步
+#[derive(Debug, Clone)]
+pub struct ClockSync {
+    /// 配置参数
+    config: ClockSyncConfig,
+    /// 上一个设备时间戳(毫秒)
+    last_raw: Option<u16>,
+    /// 上一个数据包的接收时刻
+    last_received: u64,
+    /// 展开后的设备时间(微秒)
+    device: u64,
+    /// 已结束的分组中各自偏移最小的样本
+    buckets: Deque<Sample, MAX_BUCKETS>,
+    /// 当前分组中偏移最小的样本
+    current: Option<Sample>,
+    /// 当前分组的起始设备时间
+    bucket_start: u64,
+    /// 回归结果
+    fit: Option<Fit>,
+    /// 检测到的跳变次数
+    resets: u32,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockSync {
+    /// 创建新的实例,使用默认配置
+    pub const fn new() -> Self {
+        Self {
+            config: ClockSyncConfig::DEFAULT,
+            last_raw: None,
+            last_received: 0,
+            device: 0,
+            buckets: Deque::new(),
+            current: None,
+            bucket_start: 0,
+            fit: None,
+            resets: 0,
+        }
+    }
+
+    /// 使用指定配置创建实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ClockSyncConfigError`]
+    pub fn with_config(config: ClockSyncConfig) -> Result<Self, ClockSyncConfigError> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            ..Self::new()
+        })
+    }
+
+    /// 丢弃所有历史数据,下一个时间戳重新作为起点
+    pub fn reset(&mut self) {
+        *self = Self {
+            config: self.config.clone(),
+            ..Self::new()
+        };
+    }
+
+    /// 最近一个数据包展开后的设备时间(微秒),单调递增
+    pub fn device_time(&self) -> u64 {
+        self.device
+    }
+
+    /// 检测到的时间戳跳变次数
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// MCU时钟相对设备时钟的速率偏差,例如1e-4表示MCU时钟快100ppm
+    pub fn drift(&self) -> f32 {
+        self.fit.map_or(0.0, |fit| fit.drift_ppb as f32 * 1e-9)
+    }
+
+    /// 将展开后的设备时间换算为MCU时刻,尚未收到任何数据包时原样返回
+    pub fn to_host(&self, device: u64) -> u64 {
+        let offset = match (self.fit, self.current) {
+            (Some(fit), _) => {
+                let elapsed = device as i64 - fit.reference as i64;
+                fit.offset + elapsed * fit.drift_ppb / 1_000_000_000
+            }
+            (None, Some(sample)) => sample.offset,
+            (None, None) => 0,
+        };
+        (device as i64 + offset).max(0) as u64
+    }
+
+    /// 输入一个设备时间戳及其接收时刻,返回该设备时间戳对应的MCU时刻
+    ///
+    /// # Arguments
+    /// * `device_ms` - 设备时间戳(毫秒)
+    /// * `received` - 接收时刻(微秒)
+    pub fn update(&mut self, device_ms: u16, received: u64) -> u64 {
+        let period_ms = self.config.period_ms as u64;
+        let period = period_ms * 1000;
+        if let Some(last) = self.last_raw {
+            let elapsed = received.saturating_sub(self.last_received);
+            let raw = device_ms as u64 % period_ms * 1000;
+            let last = last as u64 % period_ms * 1000;
+            // 根据接收间隔判断回绕的次数,长时间没有数据时也能正确展开
+            let mut delta = (raw + period - last) % period;
+            if elapsed > delta {
+                delta += (elapsed - delta + period / 2) / period * period;
+            }
+            if delta.abs_diff(elapsed) > self.config.jump_threshold {
+                self.restart();
+                delta = elapsed;
+            }
+            self.device += delta;
+        }
+        self.last_raw = Some(device_ms);
+        self.last_received = received;
+
+        let sample = Sample {
+            device: self.device,
+            offset: received as i64 - self.device as i64,
+        };
+        match self.current {
+            Some(current) if self.device - self.bucket_start >= self.config.bucket => {
+                self.close_bucket(current);
+                self.current = Some(sample);
+                self.bucket_start = self.device;
+            }
+            Some(current) if sample.offset < current.offset => self.current = Some(sample),
+            Some(_) => {}
+            None => {
+                self.current = Some(sample);
+                self.bucket_start = self.device;
+            }
+        }
+        self.to_host(self.device)
+    }
+
+    /// 时间戳跳变后丢弃历史数据,保留展开后的设备时间
+    fn restart(&mut self) {
+        self.buckets.clear();
+        self.current = None;
+        self.fit = None;
+        self.resets += 1;
+    }
+
+    /// 结束当前分组并重新回归
+    ///
+    /// 每组只回归一次,为保证精度使用f64,结果转换为整数供每个数据包换算使用
+    fn close_bucket(&mut self, sample: Sample) {
+        if self.buckets.is_full() {
+            self.buckets.pop_front();
+        }
+        self.buckets.push_back(sample).ok();
+
+        let reference = sample.device;
+        let n = self.buckets.len() as f64;
+        let (mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0);
+        for s in self.buckets.iter() {
+            let x = (s.device as i64 - reference as i64) as f64;
+            let y = s.offset as f64;
+            sx += x;
+            sy += y;
+            sxx += x * x;
+            sxy += x * y;
+        }
+        let denominator = n * sxx - sx * sx;
+        let drift = if denominator > 0.0 {
+            (n * sxy - sx * sy) / denominator
+        } else {
+            0.0
+        };
+        self.fit = Some(Fit {
+            reference,
+            offset: ((sy - drift * sx) / n) as i64,
+            drift_ppb: (drift * 1e9) as i64,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟设备时钟慢100ppm,传输延迟为200us加伪随机抖动
+    fn simulate(sync: &mut ClockSync, packets: core::ops::Range<u64>, start_ms: u64) -> u64 {
+        let mut worst = 0;
+        for i in packets.clone() {
+            let t = i * 2_700;
+            let device_ms = ((t as f64 * (1.0 - 1e-4) / 1000.0) as u64 + start_ms) % 30000;
+            let jitter = if i % 10 == 0 { 0 } else { (i * 7919) % 800 };
+            let host = sync.update(device_ms as u16, t + 200 + jitter);
+            if i > packets.start + 2_000 {
+                worst = worst.max(host.abs_diff(t + 200));
+            }
+        }
+        worst
+    }
+
+    #[test]
+    fn test_sync_across_rollover() {
+        let mut sync = ClockSync::new();
+        // 运行70秒,期间设备时间戳回绕两次
+        let worst = simulate(&mut sync, 0..26_000, 7000);
+        assert!((sync.drift() - 1e-4).abs() < 2e-5, "{}", sync.drift());
+        assert!(worst < 1_200, "{worst}");
+        assert_eq!(sync.resets(), 0);
+        assert!(sync.device_time() > 69_000_000);
+    }
+
+    #[test]
+    fn test_long_gap_is_unwrapped() {
+        let mut sync = ClockSync::new();
+        sync.update(1000, 1_000_000);
+        // 70秒后收到下一个数据包,期间回绕两次
+        sync.update(11_000, 71_000_000);
+        assert_eq!(sync.device_time(), 70_000_000);
+        assert_eq!(sync.resets(), 0);
+    }
+
+    #[test]
+    fn test_reset_is_detected() {
+        let mut sync = ClockSync::new();
+        simulate(&mut sync, 0..5_000, 12_000);
+        let before = sync.device_time();
+        // 雷达重新上电,时间戳发生跳变
+        let worst = simulate(&mut sync, 5_000..10_000, 0);
+        assert_eq!(sync.resets(), 1);
+        assert!(sync.device_time() > before);
+        assert!(worst < 1_200, "{worst}");
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = ClockSyncConfig {
+            jump_threshold: 20_000_000,
+            ..ClockSyncConfig::default()
+        };
+        assert_eq!(
+            ClockSync::with_config(config).err(),
+            Some(ClockSyncConfigError::JumpThreshold)
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! 距离和角度扇区屏蔽
+//!
+//! 雷达每圈都会扫到安装在机器人上的立柱、防撞条等固定结构。屏蔽滤波器丢弃或标记
+//! 全局距离范围以外的点,以及落在指定角度扇区和距离范围内的点。
+//!
+//! 配置可以用 [`MaskConfig::encode`] 编码为字节序列保存到Flash等存储中,
+//! 再用 [`MaskConfig::decode`] 读回
+
+use heapless::Vec;
+
+use super::ScanFilter;
+use crate::angle::{self, Angle};
+use crate::filter::slbf::PointData;
+use crate::scan::Scan;
+
+/// 最多可配置的扇区数
+pub const MAX_SECTORS: usize = 8;
+
+/// 编码格式版本
+const FORMAT_VERSION: u8 = 1;
+/// 编码后的头部长度
+const HEADER_LEN: usize = 7;
+/// 每个扇区编码后的长度
+const SECTOR_LEN: usize = 8;
+/// 编码后的最大长度
+pub const MAX_ENCODED_LEN: usize = HEADER_LEN + MAX_SECTORS * SECTOR_LEN;
+
+/// 被屏蔽的点的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MaskAction {
+    /// 从扫描数据中删除
+    Drop,
+    /// 保留在原位置,距离置0表示无效
+    Flag,
+}
+
+/// 屏蔽扇区
+///
+/// 从 `start` 顺时针到 `end`(含两端)的角度范围内,距离在 `min_range..=max_range` 的点被屏蔽。
+/// `start` 大于 `end` 时扇区跨越0度
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sector {
+    /// 起始角度
+    pub start: Angle,
+    /// 结束角度
+    pub end: Angle,
+    /// 屏蔽的最小距离(mm)
+    pub min_range: u16,
+    /// 屏蔽的最大距离(mm)
+    pub max_range: u16,
+}
+
+impl Sector {
+    /// 创建屏蔽整个距离范围的扇区
+    pub fn new(start: Angle, end: Angle) -> Self {
+        Self {
+            start,
+            end,
+            min_range: 0,
+            max_range: u16::MAX,
+        }
+    }
+
+    /// 创建只屏蔽指定距离范围的扇区
+    pub fn with_range(start: Angle, end: Angle, min_range: u16, max_range: u16) -> Self {
+        Self {
+            start,
+            end,
+            min_range,
+            max_range,
+        }
+    }
+
+    /// 角度是否在扇区内
+    pub fn contains_angle(&self, angle: Angle) -> bool {
+        if self.start <= self.end {
+            self.start <= angle && angle <= self.end
+        } else {
+            angle >= self.start || angle <= self.end
+        }
+    }
+
+    /// 点是否被该扇区屏蔽
+    pub fn masks(&self, point: &PointData) -> bool {
+        self.contains_angle(point.angle)
+            && (self.min_range..=self.max_range).contains(&point.distance)
+    }
+}
+
+/// 屏蔽配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MaskConfigError {
+    /// 最小距离大于最大距离
+    RangeOrder,
+    /// 扇区角度无效
+    SectorAngle,
+    /// 扇区数超过 [`MAX_SECTORS`]
+    TooManySectors,
+    /// 编码缓冲区太小
+    BufferTooSmall,
+    /// 编码数据长度或版本不正确
+    InvalidData,
+}
+
+/// 屏蔽配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MaskConfig {
+    /// 全局最小有效距离(mm),更近的点被屏蔽
+    pub min_range: u16,
+    /// 全局最大有效距离(mm),更远的点被屏蔽
+    pub max_range: u16,
+    /// 屏蔽扇区
+    pub sectors: Vec<Sector, MAX_SECTORS>,
+    /// 被屏蔽的点的处理方式
+    pub action: MaskAction,
+}
+
+impl Default for MaskConfig {
+    fn default() -> Self {
+        Self {
+            min_range: 0,
+            max_range: u16::MAX,
+            sectors: Vec::new(),
+            action: MaskAction::Drop,
+        }
+    }
+}
+
+impl MaskConfig {
+    /// 添加一个屏蔽扇区
+    pub fn add_sector(&mut self, sector: Sector) -> Result<(), MaskConfigError> {
+        self.sectors
+            .push(sector)
+            .map_err(|_| MaskConfigError::TooManySectors)
+    }
+
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), MaskConfigError> {
+        if self.min_range > self.max_range {
+            return Err(MaskConfigError::RangeOrder);
+        }
+        for sector in &self.sectors {
+            if !angle::is_valid(sector.start) || !angle::is_valid(sector.end) {
+                return Err(MaskConfigError::SectorAngle);
+            }
+            if sector.min_range > sector.max_range {
+                return Err(MaskConfigError::RangeOrder);
+            }
+        }
+        Ok(())
+    }
+
+    /// 编码为字节序列,多字节字段为小端字节序,角度以0.01度为单位
+    ///
+    /// # Returns
+    /// * 写入 `buf` 的字节数,最多为 [`MAX_ENCODED_LEN`]
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, MaskConfigError> {
+        let len = HEADER_LEN + self.sectors.len() * SECTOR_LEN;
+        if buf.len() < len {
+            return Err(MaskConfigError::BufferTooSmall);
+        }
+
+        buf[0] = FORMAT_VERSION;
+        buf[1] = match self.action {
+            MaskAction::Drop => 0,
+            MaskAction::Flag => 1,
+        };
+        buf[2..4].copy_from_slice(&self.min_range.to_le_bytes());
+        buf[4..6].copy_from_slice(&self.max_range.to_le_bytes());
+        buf[6] = self.sectors.len() as u8;
+        for (sector, chunk) in self
+            .sectors
+            .iter()
+            .zip(buf[HEADER_LEN..len].chunks_exact_mut(SECTOR_LEN))
+        {
+            chunk[0..2].copy_from_slice(&angle::to_centidegrees(sector.start).to_le_bytes());
+            chunk[2..4].copy_from_slice(&angle::to_centidegrees(sector.end).to_le_bytes());
+            chunk[4..6].copy_from_slice(&sector.min_range.to_le_bytes());
+            chunk[6..8].copy_from_slice(&sector.max_range.to_le_bytes());
+        }
+        Ok(len)
+    }
+
+    /// 从 [`MaskConfig::encode`] 生成的字节序列解码,并检查参数是否合理
+    pub fn decode(buf: &[u8]) -> Result<Self, MaskConfigError> {
+        if buf.len() < HEADER_LEN || buf[0] != FORMAT_VERSION {
+            return Err(MaskConfigError::InvalidData);
+        }
+        let count = buf[6] as usize;
+        if count > MAX_SECTORS {
+            return Err(MaskConfigError::TooManySectors);
+        }
+        if buf.len() != HEADER_LEN + count * SECTOR_LEN {
+            return Err(MaskConfigError::InvalidData);
+        }
+
+        let read_u16 = |bytes: &[u8]| u16::from_le_bytes([bytes[0], bytes[1]]);
+        let mut config = Self {
+            min_range: read_u16(&buf[2..]),
+            max_range: read_u16(&buf[4..]),
+            sectors: Vec::new(),
+            action: match buf[1] {
+                0 => MaskAction::Drop,
+                1 => MaskAction::Flag,
+                _ => return Err(MaskConfigError::InvalidData),
+            },
+        };
+        for chunk in buf[HEADER_LEN..].chunks_exact(SECTOR_LEN) {
+            config.add_sector(Sector {
+                start: angle::from_centidegrees(read_u16(&chunk[0..])),
+                end: angle::from_centidegrees(read_u16(&chunk[2..])),
+                min_range: read_u16(&chunk[4..]),
+                max_range: read_u16(&chunk[6..]),
+            })?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// 距离和角度扇区屏蔽滤波器
+pub struct MaskFilter {
+    /// 配置参数
+    config: MaskConfig,
+}
+
+impl MaskFilter {
+    /// 创建新的滤波器实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`MaskConfigError`]
+    pub fn new(config: MaskConfig) -> Result<Self, MaskConfigError> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> &MaskConfig {
+        &self.config
+    }
+
+    /// 点是否被屏蔽
+    pub fn is_masked(&self, point: &PointData) -> bool {
+        !(self.config.min_range..=self.config.max_range).contains(&point.distance)
+            || self.config.sectors.iter().any(|sector| sector.masks(point))
+    }
+
+    /// 原地屏蔽点云数据
+    ///
+    /// # Returns
+    /// * 被屏蔽的点数。距离已经为0的无效点保持不变,不计入
+    pub fn mask_in_place<const N: usize>(&self, points: &mut Vec<PointData, N>) -> usize {
+        let count = points.len();
+        match self.config.action {
+            MaskAction::Drop => {
+                points.retain(|point| point.distance == 0 || !self.is_masked(point));
+                count - points.len()
+            }
+            MaskAction::Flag => {
+                let mut masked = 0;
+                for point in points.iter_mut() {
+                    if point.distance != 0 && self.is_masked(point) {
+                        point.distance = 0;
+                        masked += 1;
+                    }
+                }
+                masked
+            }
+        }
+    }
+}
+
+impl<const N: usize> ScanFilter<N> for MaskFilter {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.mask_in_place(&mut scan.points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::slbf::Slbf;
+    use crate::filter::FilterChain;
+
+    /// 0度起每隔1度一个点,距离均为 `distance`
+    fn scan(distance: u16) -> Scan<512> {
+        let mut scan = Scan::default();
+        for i in 0..360u16 {
+            scan.points
+                .push(PointData {
+                    angle: angle::from_centidegrees(i * 100),
+                    distance,
+                    intensity: 220,
+                    timestamp: i as u64,
+                })
+                .unwrap();
+        }
+        scan.speed = 3600;
+        scan
+    }
+
+    fn config() -> MaskConfig {
+        let mut config = MaskConfig {
+            min_range: 50,
+            max_range: 8000,
+            ..MaskConfig::default()
+        };
+        // 车身立柱,只屏蔽300mm以内
+        config
+            .add_sector(Sector::with_range(
+                angle::from_degrees(40.0),
+                angle::from_degrees(49.0),
+                0,
+                300,
+            ))
+            .unwrap();
+        // 后方跨越0度的防撞条
+        config
+            .add_sector(Sector::new(
+                angle::from_degrees(355.0),
+                angle::from_degrees(4.0),
+            ))
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn test_drop_sectors() {
+        let mut filter = MaskFilter::new(config()).unwrap();
+
+        let mut near = scan(200);
+        filter.apply(&mut near);
+        assert_eq!(near.len(), 360 - 10 - 10);
+        assert!(near
+            .points
+            .iter()
+            .all(|p| !(40..=49).contains(&p.timestamp)));
+
+        // 立柱扇区只屏蔽近处,远处的点保留
+        let mut far = scan(1000);
+        filter.apply(&mut far);
+        assert_eq!(far.len(), 360 - 10);
+
+        // 超出全局距离范围
+        let mut out_of_range = scan(9000);
+        filter.apply(&mut out_of_range);
+        assert!(out_of_range.is_empty());
+    }
+
+    #[test]
+    fn test_flag_keeps_points() {
+        let config = MaskConfig {
+            action: MaskAction::Flag,
+            ..config()
+        };
+        let filter = MaskFilter::new(config).unwrap();
+        let mut scan = scan(200);
+        assert_eq!(filter.mask_in_place(&mut scan.points), 20);
+        assert_eq!(scan.len(), 360);
+        assert_eq!(scan.points[0].distance, 0);
+        assert_eq!(scan.points[40].distance, 0);
+        assert_eq!(scan.points[50].distance, 200);
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let config = config();
+        let mut buf = [0u8; MAX_ENCODED_LEN];
+        let len = config.encode(&mut buf).unwrap();
+        assert_eq!(len, HEADER_LEN + 2 * SECTOR_LEN);
+        assert_eq!(MaskConfig::decode(&buf[..len]), Ok(config));
+
+        assert_eq!(
+            MaskConfig::decode(&buf[..len - 1]),
+            Err(MaskConfigError::InvalidData)
+        );
+        let mut small = [0u8; 8];
+        assert_eq!(
+            MaskConfig::default().encode(&mut small[..4]),
+            Err(MaskConfigError::BufferTooSmall)
+        );
+        // 最小距离大于最大距离
+        buf[2..4].copy_from_slice(&9000u16.to_le_bytes());
+        assert_eq!(
+            MaskConfig::decode(&buf[..len]),
+            Err(MaskConfigError::RangeOrder)
+        );
+    }
+
+    #[test]
+    fn test_compose_with_slbf() {
+        let mut chain =
+            FilterChain::new(MaskFilter::new(config()).unwrap(), Slbf::new(3600.0, false));
+        let mut scan = scan(200);
+        chain.apply(&mut scan);
+        assert_eq!(scan.len(), 340);
+    }
+}
@@ -0,0 +1,231 @@
+//! 角度中值滤波
+//!
+//! 用相邻若干个点距离的中值替换每个点的距离,抑制平面上的测距抖动。
+//! 与中心点距离相差过大或角度不连续的相邻点不参与计算,以保留物体边缘
+
+use heapless::Vec;
+
+use super::ScanFilter;
+use crate::angle::{self, Angle};
+use crate::filter::slbf::PointData;
+use crate::scan::Scan;
+
+/// 最大窗口大小
+pub const MAX_WINDOW: usize = 9;
+
+/// 中值滤波配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MedianConfig {
+    /// 窗口大小(点数),必须为不超过 [`MAX_WINDOW`] 的奇数
+    pub window: usize,
+    /// 相邻点与中心点的距离差超过该值(mm)时视为物体边缘,不参与计算
+    pub edge_threshold: u16,
+    /// 相邻两点间允许的最大角度差,超过时视为数据不连续
+    pub max_gap: Angle,
+}
+
+impl Default for MedianConfig {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            edge_threshold: 100,
+            max_gap: angle::from_degrees(2.0),
+        }
+    }
+}
+
+/// 中值滤波配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MedianConfigError {
+    /// 窗口大小不是奇数或超过 [`MAX_WINDOW`]
+    Window,
+    /// 最大角度差无效
+    MaxGap,
+}
+
+impl MedianConfig {
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), MedianConfigError> {
+        if self.window % 2 != 1 || self.window > MAX_WINDOW {
+            return Err(MedianConfigError::Window);
+        }
+        if !angle::is_valid(self.max_gap) {
+            return Err(MedianConfigError::MaxGap);
+        }
+        Ok(())
+    }
+}
+
+/// 角度中值滤波器
+pub struct MedianFilter {
+    /// 配置参数
+    config: MedianConfig,
+}
+
+impl MedianFilter {
+    /// 创建新的滤波器实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`MedianConfigError`]
+    pub fn new(config: MedianConfig) -> Result<Self, MedianConfigError> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 原地滤波,点云应按角度排序,一圈首尾相接
+    ///
+    /// 距离为0的无效点保持不变,也不参与相邻点的计算
+    pub fn filter_in_place(&self, points: &mut [PointData]) {
+        let half = self.config.window / 2;
+        let n = points.len();
+        if half == 0 || n < self.config.window {
+            return;
+        }
+
+        // 开头和最近处理过的点的原始距离,处理到它们的相邻点时已被覆盖
+        let mut head = [0u16; MAX_WINDOW / 2];
+        for (slot, point) in head.iter_mut().zip(points.iter()) {
+            *slot = point.distance;
+        }
+        let mut recent = [0u16; MAX_WINDOW / 2];
+
+        for i in 0..n {
+            let center = points[i].distance;
+            if center != 0 {
+                let mut values = Vec::<u16, MAX_WINDOW>::new();
+                values.push(center).ok();
+                for offset in 1..=half {
+                    for (j, wrapped) in [
+                        ((i + n - offset) % n, offset > i),
+                        ((i + offset) % n, i + offset >= n),
+                    ] {
+                        let distance = match (j < i, wrapped) {
+                            // 左侧已处理的点
+                            (true, false) => recent[j % half],
+                            // 右侧越过结尾回到开头的点
+                            (true, true) => head[j],
+                            _ => points[j].distance,
+                        };
+                        if self.is_neighbor(&points[i], &points[j], distance, offset) {
+                            values.push(distance).ok();
+                        }
+                    }
+                }
+                values.sort_unstable();
+                points[i].distance = values[values.len() / 2];
+            }
+            recent[i % half] = center;
+        }
+    }
+
+    /// 相邻点是否参与中心点的计算
+    fn is_neighbor(
+        &self,
+        center: &PointData,
+        other: &PointData,
+        distance: u16,
+        offset: usize,
+    ) -> bool {
+        distance != 0
+            && distance.abs_diff(center.distance) <= self.config.edge_threshold
+            && angle::separation(center.angle, other.angle)
+                <= angle::saturating_mul(self.config.max_gap, offset as u16)
+    }
+}
+
+impl<const N: usize> ScanFilter<N> for MedianFilter {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.filter_in_place(&mut scan.points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0度起每隔1度一个点
+    fn points(distances: &[u16]) -> std::vec::Vec<PointData> {
+        distances
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| PointData {
+                angle: angle::from_centidegrees(i as u16 * 100),
+                distance,
+                intensity: 200,
+                timestamp: 0,
+            })
+            .collect()
+    }
+
+    fn distances(points: &[PointData]) -> std::vec::Vec<u16> {
+        points.iter().map(|p| p.distance).collect()
+    }
+
+    #[test]
+    fn test_median_removes_jitter() {
+        let filter = MedianFilter::new(MedianConfig::default()).unwrap();
+        let mut points = points(&[1000; 360]);
+        for (point, distance) in points[100..]
+            .iter_mut()
+            .zip([1010, 990, 1040, 1000, 995, 1005])
+        {
+            point.distance = distance;
+        }
+        filter.filter_in_place(&mut points);
+        assert!(points.iter().all(|p| p.distance == 1000));
+    }
+
+    #[test]
+    fn test_median_preserves_edges_and_invalid_points() {
+        let filter = MedianFilter::new(MedianConfig::default()).unwrap();
+        // 1000mm的墙和2000mm的墙相接,中间有一个无效点
+        let input = [1000, 1000, 1000, 0, 2000, 2000, 2000, 2000];
+        let mut points = points(&input);
+        filter.filter_in_place(&mut points);
+        assert_eq!(distances(&points), input);
+    }
+
+    #[test]
+    fn test_median_across_wraparound() {
+        let filter = MedianFilter::new(MedianConfig {
+            window: 3,
+            ..MedianConfig::default()
+        })
+        .unwrap();
+        // 只有首尾相邻时,第一个和最后一个点的毛刺才能被消除
+        let mut points = points(&[1000; 360]);
+        points[0].distance = 1050;
+        points[359].distance = 960;
+        filter.filter_in_place(&mut points);
+        assert_eq!(points[0].distance, 1000);
+        assert_eq!(points[359].distance, 1000);
+    }
+
+    #[test]
+    fn test_large_max_gap() {
+        // 定点表示下170度乘以4超出u16范围
+        let filter = MedianFilter::new(MedianConfig {
+            window: MAX_WINDOW,
+            max_gap: angle::from_degrees(170.0),
+            ..MedianConfig::default()
+        })
+        .unwrap();
+        let mut points = points(&[1000, 1000, 1000, 1000, 1050, 1000, 1000, 1000, 1000]);
+        filter.filter_in_place(&mut points);
+        assert!(points.iter().all(|p| p.distance == 1000));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = MedianConfig {
+            window: 4,
+            ..MedianConfig::default()
+        };
+        assert_eq!(
+            MedianFilter::new(config).err(),
+            Some(MedianConfigError::Window)
+        );
+    }
+}
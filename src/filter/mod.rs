@@ -1 +1,145 @@
-pub mod slbf;
\ No newline at end of file
+//! 点云滤波
+//!
+//! 所有滤波器都实现 [`ScanFilter`],在一圈扫描的缓冲区上原地处理,不分配内存。
+//! 多个滤波器可以用 [`FilterChain`] 在编译期组合,也可以放在
+//! `[&mut dyn ScanFilter<N>]` 列表中在运行时组合
+
+pub mod mask;
+pub mod median;
+pub mod shadow;
+pub mod slbf;
+pub mod temporal;
+
+use crate::scan::Scan;
+
+/// 扫描数据滤波器
+///
+/// `N` 为扫描缓冲区的容量,与 [`crate::scan::ScanAssembler`] 的容量相同
+pub trait ScanFilter<const N: usize> {
+    /// 原地处理一圈扫描数据,可以修改、删除或重新排列其中的点
+    fn apply(&mut self, scan: &mut Scan<N>);
+}
+
+impl<const N: usize, F: ScanFilter<N> + ?Sized> ScanFilter<N> for &mut F {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        (**self).apply(scan);
+    }
+}
+
+/// 按顺序运行列表中的每个滤波器,用于运行时组合
+impl<const N: usize, F: ScanFilter<N>> ScanFilter<N> for [F] {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        for filter in self {
+            filter.apply(scan);
+        }
+    }
+}
+
+/// 依次运行两个滤波器
+///
+/// 可以嵌套组成任意长度的滤波链,例如 `FilterChain::new(range, intensity).then(slbf)`
+pub struct FilterChain<A, B> {
+    /// 先运行的滤波器
+    first: A,
+    /// 后运行的滤波器
+    second: B,
+}
+
+impl<A, B> FilterChain<A, B> {
+    /// 创建新的滤波链
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// 在滤波链末尾接上另一个滤波器
+    pub fn then<C>(self, next: C) -> FilterChain<Self, C> {
+        FilterChain::new(self, next)
+    }
+
+    /// 先运行的滤波器
+    pub fn first_mut(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    /// 后运行的滤波器
+    pub fn second_mut(&mut self) -> &mut B {
+        &mut self.second
+    }
+
+    /// 拆分为两个滤波器
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<const N: usize, A: ScanFilter<N>, B: ScanFilter<N>> ScanFilter<N> for FilterChain<A, B> {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.first.apply(scan);
+        self.second.apply(scan);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::angle;
+    use crate::filter::slbf::{PointData, Slbf};
+
+    /// 丢弃距离超过上限的点
+    struct MaxDistance(u16);
+
+    impl<const N: usize> ScanFilter<N> for MaxDistance {
+        fn apply(&mut self, scan: &mut Scan<N>) {
+            scan.points.retain(|p| p.distance <= self.0);
+        }
+    }
+
+    /// 记录被调用的顺序
+    struct Record<'a>(&'a core::cell::RefCell<std::vec::Vec<u8>>, u8);
+
+    impl<const N: usize> ScanFilter<N> for Record<'_> {
+        fn apply(&mut self, _scan: &mut Scan<N>) {
+            self.0.borrow_mut().push(self.1);
+        }
+    }
+
+    /// 0度起每隔0.8度一个点,距离依次递增
+    fn scan() -> Scan<64> {
+        let mut scan = Scan::default();
+        for i in 0..20u16 {
+            scan.points
+                .push(PointData {
+                    angle: angle::from_centidegrees(i * 80),
+                    distance: 800 + i * 100,
+                    intensity: 220,
+                    timestamp: 0,
+                })
+                .unwrap();
+        }
+        scan.speed = 3600;
+        scan
+    }
+
+    #[test]
+    fn test_chain_runs_in_order() {
+        let log = core::cell::RefCell::new(std::vec::Vec::new());
+        let mut chain = FilterChain::new(Record(&log, 1), Record(&log, 2)).then(Record(&log, 3));
+        ScanFilter::<64>::apply(&mut chain, &mut scan());
+        assert_eq!(*log.borrow(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_compile_time_and_runtime_chains_agree() {
+        let mut chain = FilterChain::new(MaxDistance(2000), Slbf::new(3600.0, true));
+        let mut expected = scan();
+        chain.apply(&mut expected);
+        assert_eq!(expected.len(), 13);
+
+        let mut max_distance = MaxDistance(2000);
+        let mut slbf = Slbf::new(3600.0, true);
+        let mut list: [&mut dyn ScanFilter<64>; 2] = [&mut max_distance, &mut slbf];
+        let mut actual = scan();
+        list[..].apply(&mut actual);
+        assert_eq!(actual.len(), expected.len());
+    }
+}
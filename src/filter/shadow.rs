@@ -0,0 +1,243 @@
+//! 阴影点滤波
+//!
+//! 激光光斑同时落在前景物体边缘和背景上时,雷达会测出位于两者之间的"拖尾"点,
+//! 在路径规划中形成并不存在的障碍物。
+//!
+//! 与ROS laser_filters的ScanShadowsFilter相同,对每个点和它的相邻点,计算从该点指向
+//! 相邻点的线段与该点所在射线的夹角。夹角小于 `min_angle` 或大于 `max_angle` 时,
+//! 说明这段"表面"几乎与射线平行,其中较远的点被视为阴影点删除
+
+use heapless::Vec;
+
+use super::ScanFilter;
+use crate::angle::{self, Angle};
+use crate::filter::slbf::PointData;
+use crate::scan::Scan;
+
+/// 最大相邻点窗口
+pub const MAX_WINDOW: usize = 8;
+
+/// 阴影点滤波配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ShadowConfig {
+    /// 最小夹角,小于该角度的点视为阴影点
+    pub min_angle: Angle,
+    /// 最大夹角,大于该角度的点视为阴影点
+    pub max_angle: Angle,
+    /// 每侧参与比较的相邻点数,不超过 [`MAX_WINDOW`]
+    pub window: usize,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            min_angle: angle::from_degrees(10.0),
+            max_angle: angle::from_degrees(170.0),
+            window: 1,
+        }
+    }
+}
+
+/// 阴影点滤波配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ShadowConfigError {
+    /// 夹角不满足 0 < 最小夹角 < 最大夹角 < 180度
+    AngleOrder,
+    /// 窗口为0或超过 [`MAX_WINDOW`]
+    Window,
+}
+
+impl ShadowConfig {
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), ShadowConfigError> {
+        if !(angle::ZERO < self.min_angle
+            && self.min_angle < self.max_angle
+            && self.max_angle < angle::HALF_TURN)
+        {
+            return Err(ShadowConfigError::AngleOrder);
+        }
+        if self.window == 0 || self.window > MAX_WINDOW {
+            return Err(ShadowConfigError::Window);
+        }
+        Ok(())
+    }
+}
+
+/// 阴影点滤波器
+pub struct ShadowFilter {
+    /// 配置参数
+    config: ShadowConfig,
+    /// 最小夹角的正弦和余弦
+    min_sin_cos: (f32, f32),
+    /// 最大夹角的正弦和余弦
+    max_sin_cos: (f32, f32),
+}
+
+impl ShadowFilter {
+    /// 创建新的滤波器实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ShadowConfigError`]
+    pub fn new(config: ShadowConfig) -> Result<Self, ShadowConfigError> {
+        config.validate()?;
+        Ok(Self {
+            min_sin_cos: angle::sin_cos(config.min_angle),
+            max_sin_cos: angle::sin_cos(config.max_angle),
+            config,
+        })
+    }
+
+    /// 原地删除阴影点,点云应按角度排序,一圈首尾相接
+    ///
+    /// # Returns
+    /// * 删除的点数
+    pub fn filter_in_place<const N: usize>(&self, points: &mut Vec<PointData, N>) -> usize {
+        let window = self.config.window;
+        let n = points.len();
+        if n <= 2 * window {
+            return 0;
+        }
+
+        // 每个点的判断依赖前后 `window` 个点的原始距离,标记结果延后写入:
+        // 开头的点要等到最后才能置0,其余的点在不再被用到时置0
+        let mut head = [false; MAX_WINDOW];
+        let mut recent = [false; MAX_WINDOW + 1];
+        for i in 0..n {
+            let shadow = self.is_shadow(points, i);
+            if i < window {
+                head[i] = shadow;
+            } else {
+                recent[i % (window + 1)] = shadow;
+            }
+
+            if i >= 2 * window && recent[(i - window) % (window + 1)] {
+                points[i - window].distance = 0;
+            }
+        }
+        for i in n - window..n {
+            if recent[i % (window + 1)] {
+                points[i].distance = 0;
+            }
+        }
+        for (i, &shadow) in head.iter().enumerate().take(window) {
+            if shadow {
+                points[i].distance = 0;
+            }
+        }
+
+        let count = points.len();
+        points.retain(|point| point.distance != 0);
+        count - points.len()
+    }
+
+    /// 第 `i` 个点是否为阴影点
+    fn is_shadow(&self, points: &[PointData], i: usize) -> bool {
+        let n = points.len();
+        let point = &points[i];
+        if point.distance == 0 {
+            return false;
+        }
+        (1..=self.config.window)
+            .flat_map(|offset| [(i + n - offset) % n, (i + offset) % n])
+            .any(|j| {
+                let other = &points[j];
+                // 只删除较远的点,保留前景物体的边缘
+                other.distance != 0
+                    && point.distance > other.distance
+                    && self.is_steep(point, other)
+            })
+    }
+
+    /// 从 `point` 指向 `other` 的线段与 `point` 所在射线的夹角是否超出范围
+    fn is_steep(&self, point: &PointData, other: &PointData) -> bool {
+        let (sin, cos) = angle::sin_cos(angle::separation(point.angle, other.angle));
+        let r1 = point.distance as f32;
+        let r2 = other.distance as f32;
+        // 以 `point` 为原点、射线反方向为x轴时 `other` 的坐标,y不小于0
+        let x = r1 - r2 * cos;
+        let y = r2 * sin;
+        // 夹角小于a等价于 (x, y) 在方向 (cos a, sin a) 的顺时针一侧
+        let (min_sin, min_cos) = self.min_sin_cos;
+        let (max_sin, max_cos) = self.max_sin_cos;
+        x * min_sin - y * min_cos > 0.0 || x * max_sin - y * max_cos < 0.0
+    }
+}
+
+impl<const N: usize> ScanFilter<N> for ShadowFilter {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.filter_in_place(&mut scan.points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0度起每隔0.8度一个点
+    fn points(distances: &[u16]) -> Vec<PointData, 512> {
+        distances
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| PointData {
+                angle: angle::from_centidegrees(i as u16 * 80),
+                distance,
+                intensity: 200,
+                timestamp: i as u64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_remove_veiling_points() {
+        let filter = ShadowFilter::new(ShadowConfig::default()).unwrap();
+
+        // 1000mm处的前景物体和3000mm处的背景之间有两个拖尾点
+        let mut distances = [3000u16; 450];
+        distances[100..120].fill(1000);
+        distances[120] = 1600;
+        distances[121] = 2300;
+        let mut points = points(&distances);
+
+        // 紧挨着前景边缘的背景点同样被删除
+        assert_eq!(filter.filter_in_place(&mut points), 4);
+        let removed = [99, 120, 121, 122];
+        assert!(points.iter().all(|p| !removed.contains(&p.timestamp)));
+        // 前景物体的边缘保留
+        assert!(points.iter().any(|p| p.timestamp == 100));
+        assert!(points.iter().any(|p| p.timestamp == 119));
+    }
+
+    #[test]
+    fn test_wall_is_kept() {
+        let filter = ShadowFilter::new(ShadowConfig {
+            window: 3,
+            ..ShadowConfig::default()
+        })
+        .unwrap();
+
+        // 正前方1000mm处的墙,距离随角度缓慢变化
+        let distances: std::vec::Vec<u16> = (0..450)
+            .map(|i| {
+                let offset = (i - 225i32).unsigned_abs() as u16;
+                1000 + offset / 4
+            })
+            .collect();
+        let mut points = points(&distances);
+        assert_eq!(filter.filter_in_place(&mut points), 0);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = ShadowConfig {
+            min_angle: angle::from_degrees(170.0),
+            max_angle: angle::from_degrees(10.0),
+            ..ShadowConfig::default()
+        };
+        assert_eq!(
+            ShadowFilter::new(config).err(),
+            Some(ShadowConfigError::AngleOrder)
+        );
+    }
+}
@@ -1,19 +1,30 @@
+use core::cmp::Ordering;
 
+use heapless::Vec;
+
+use super::ScanFilter;
+use crate::angle::{self, Angle};
+use crate::model::LidarModel;
+use crate::scan::Scan;
 
 /// 雷达点云数据结构
-#[derive(Debug, Clone, Format)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PointData {
-    /// 角度
-    pub angle: f32,
+    /// 角度,表示方式见 [`crate::angle`]
+    pub angle: Angle,
     /// 距离
     pub distance: u16,
     /// 强度
     pub intensity: u8,
-    // 时间戳
+    /// 采样时刻,MCU或主机时钟的微秒数,由 [`crate::timing::Timestamper`] 填写,
+    /// 未填写时为0
     pub timestamp: u64,
 }
 
 /// 近距离滤波器配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SlbfConfig {
     /// 高置信度阈值
     pub confidence_high: u16,
@@ -21,8 +32,16 @@ pub struct SlbfConfig {
     pub confidence_middle: u16,
     /// 低置信度阈值
     pub confidence_low: u16,
-    /// 默认扫描频率(Hz)
+    /// 测量频率(点/秒)
     pub scan_freq: u16,
+    /// 近距离范围(mm),超出该距离的点不做过滤
+    pub near_range: u16,
+    /// 待定点组的最少点数,点数更少的组被丢弃
+    pub min_group_size: usize,
+    /// 同组相邻点最大角度差的倍数,以相邻两次测量间转过的角度为单位
+    pub gap_multiplier: f32,
+    /// 转速平滑系数,取值 `(0, 1]`,越大越跟随最新的测量值,为1时不做平滑
+    pub speed_smoothing: f32,
 }
 
 impl Default for SlbfConfig {
@@ -31,133 +50,419 @@ impl Default for SlbfConfig {
             confidence_high: 200,
             confidence_middle: 150,
             confidence_low: 92,
-            scan_freq: 2300,
+            scan_freq: LidarModel::DEFAULT.sample_rate(),
+            near_range: 1000,
+            min_group_size: 3,
+            gap_multiplier: 2.0,
+            speed_smoothing: 0.2,
+        }
+    }
+}
+
+impl SlbfConfig {
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.confidence_low > self.confidence_middle
+            || self.confidence_middle > self.confidence_high
+        {
+            return Err(ConfigError::ConfidenceOrder);
+        }
+        if self.scan_freq == 0 {
+            return Err(ConfigError::ZeroScanFreq);
+        }
+        if self.min_group_size == 0 {
+            return Err(ConfigError::ZeroGroupSize);
+        }
+        if !(self.gap_multiplier.is_finite() && self.gap_multiplier > 0.0) {
+            return Err(ConfigError::GapMultiplier);
+        }
+        if !(self.speed_smoothing > 0.0 && self.speed_smoothing <= 1.0) {
+            return Err(ConfigError::SpeedSmoothing);
         }
+        Ok(())
     }
 }
 
+/// 近距离滤波器配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// 置信度阈值不满足 低 <= 中 <= 高
+    ConfidenceOrder,
+    /// 测量频率为0
+    ZeroScanFreq,
+    /// 最少点数为0
+    ZeroGroupSize,
+    /// 角度差倍数不是正的有限值
+    GapMultiplier,
+    /// 转速平滑系数不在 `(0, 1]` 范围内
+    SpeedSmoothing,
+}
+
+/// 点的置信度分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confidence {
+    /// 直接认为有效
+    Normal,
+    /// 需要按分组进一步判断
+    Pending,
+    /// 直接丢弃
+    Discard,
+}
+
+/// 平滑后的转速,浮点表示下单位为度/秒,定点表示下为0.01度/秒
+#[cfg(not(feature = "fixed-point"))]
+type Speed = f32;
+/// 平滑后的转速,浮点表示下单位为度/秒,定点表示下为0.01度/秒
+#[cfg(feature = "fixed-point")]
+type Speed = u32;
+
+/// 定点表示下配置中的小数系数换算为Q16定点数
+#[cfg(feature = "fixed-point")]
+const Q16: f32 = 65536.0;
+
 /// 近距离滤波器
-/// 用于过滤掉近距离1m内的不合理点云数据
+/// 用于过滤掉近距离(默认1m)内的不合理点云数据
 pub struct Slbf {
-    /// 当前转速
-    curr_speed: f32,
+    /// 当前转速,平滑后的测量值
+    curr_speed: Speed,
+    /// 同组相邻点允许的最大角度差,随转速和配置更新
+    max_diff: Angle,
     /// 是否启用严格过滤策略
     enable_strict_policy: bool,
     /// 配置参数
     config: SlbfConfig,
+    /// 定点表示下的转速平滑系数(Q16),设置配置时换算,避免逐包的浮点运算
+    #[cfg(feature = "fixed-point")]
+    speed_smoothing: u32,
+    /// 定点表示下的角度差倍数(Q16)
+    #[cfg(feature = "fixed-point")]
+    gap_multiplier: u32,
 }
 
 impl Slbf {
-    /// 创建新的滤波器实例
+    /// 创建新的滤波器实例,使用默认配置
     pub fn new(speed: f32, strict_policy: bool) -> Self {
-        Self {
-            curr_speed: speed,
+        let mut filter = Self {
+            curr_speed: to_speed(speed),
+            max_diff: angle::ZERO,
             enable_strict_policy: strict_policy,
             config: SlbfConfig::default(),
+            #[cfg(feature = "fixed-point")]
+            speed_smoothing: 0,
+            #[cfg(feature = "fixed-point")]
+            gap_multiplier: 0,
+        };
+        filter.apply_config(SlbfConfig::default());
+        filter
+    }
+
+    /// 创建使用指定配置的滤波器实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ConfigError`]
+    pub fn with_config(
+        speed: f32,
+        strict_policy: bool,
+        config: SlbfConfig,
+    ) -> Result<Self, ConfigError> {
+        let mut filter = Self::new(speed, strict_policy);
+        filter.set_config(config)?;
+        Ok(filter)
+    }
+
+    /// 输入测得的转速,平滑后用于计算分组的角度差阈值
+    ///
+    /// 可以在每个数据包或每圈扫描后调用。转速为0的测量值(例如电机尚未启动)被忽略
+    ///
+    /// # Arguments
+    /// * `speed` - 数据包或一圈扫描的转速(度/秒)
+    pub fn update_speed(&mut self, speed: u16) {
+        if speed == 0 {
+            return;
+        }
+        #[cfg(not(feature = "fixed-point"))]
+        {
+            self.curr_speed += (speed as f32 - self.curr_speed) * self.config.speed_smoothing;
+        }
+        #[cfg(feature = "fixed-point")]
+        {
+            let diff = speed as i64 * 100 - self.curr_speed as i64;
+            let step = (diff * self.speed_smoothing as i64 + (1 << 15)) >> 16;
+            self.curr_speed = (self.curr_speed as i64 + step) as u32;
+        }
+        self.update_max_diff();
+    }
+
+    /// 当前使用的转速(度/秒)
+    pub fn speed(&self) -> f32 {
+        #[cfg(not(feature = "fixed-point"))]
+        {
+            self.curr_speed
+        }
+        #[cfg(feature = "fixed-point")]
+        {
+            self.curr_speed as f32 / 100.0
         }
     }
 
-    /// 1米范围内的近距离滤波,过滤不合理的数据点
+    /// 近距离范围内的滤波,过滤不合理的数据点
     /// 
+    /// 输出最多保存 `N` 个点,`N` 不小于输入点数时不会丢失点,一圈数据可以使用
+    /// [`LidarModel::max_points_per_revolution`]。需要统计无效点数时使用 [`Slbf::near_filter_in_place`]
+    ///
     /// # Arguments
     /// * `points` - 输入的点云数据
     /// 
     /// # Returns
-    /// * 过滤后的点云数据
-    pub fn near_filter(&self, points: &[PointData]) -> Vec<PointData, 360> {
-        // 分类存储点云数据
-        let mut normal = Vec::new();   // 正常点
-        let mut pending = Vec::new();  // 待处理点
-        let mut item = Vec::new();     // 临时组
-        let mut group = Vec::new();    // 有效组
-
-        // 第一步:根据距离和强度进行初步分类
+    /// * 过滤后的点云数据,按角度排序
+    pub fn near_filter<const N: usize>(&self, points: &[PointData]) -> Vec<PointData, N> {
+        let mut output = Vec::new();
         for point in points {
-            if point.distance == 0 {
-                continue;
+            if self.classify(point) != Confidence::Discard {
+                // 超出容量的点被丢弃
+                output.push(point.clone()).ok();
             }
+        }
+        self.near_filter_in_place(&mut output);
+        output
+    }
 
-            // 距离大于1000mm的点直接认为有效
-            if point.distance > 1000 {
-                normal.push(point.clone()).ok();
-                continue;
-            }
+    /// 原地进行近距离滤波,不需要额外的缓冲区
+    ///
+    /// 角度无效(NaN、无穷大或超出 `[0, 360)` 度)的点会被丢弃,不会导致panic
+    ///
+    /// # Arguments
+    /// * `points` - 点云数据,滤波后只保留有效点并按角度排序
+    ///
+    /// # Returns
+    /// * 因角度无效而丢弃的点数
+    pub fn near_filter_in_place<const N: usize>(&self, points: &mut Vec<PointData, N>) -> usize {
+        // 第一步:丢弃角度无效的点,再根据距离和强度丢弃置信度太低的点
+        let count = points.len();
+        points.retain(|point| angle::is_valid(point.angle));
+        let invalid = count - points.len();
+        points.retain(|point| self.classify(point) != Confidence::Discard);
 
-            // 根据强度进行分类
-            if point.intensity > self.config.confidence_high {
-                // 高置信度,直接认为有效
-                normal.push(point.clone()).ok();
-            } else if point.intensity > self.config.confidence_middle {
-                // 中等置信度,需要进一步判断
-                if self.enable_strict_policy {
-                    pending.push(point.clone()).ok();
-                } else {
-                    normal.push(point.clone()).ok();
-                }
-            } else if point.intensity > self.config.confidence_low {
-                // 低置信度,需要严格判断
-                pending.push(point.clone()).ok();
+        // 第二步:按角度排序。解析器输出的数据通常已经有序,只需归并少数几段
+        sort_by_angle(points);
+
+        // 第三步:待定点按角度分组,点数不足的组标记为无效
+        self.mark_small_groups(points);
+
+        // 第四步:移除被标记的点
+        points.retain(|point| point.distance != 0);
+
+        invalid
+    }
+
+    /// 根据距离和强度判断点的置信度
+    fn classify(&self, point: &PointData) -> Confidence {
+        if point.distance == 0 {
+            return Confidence::Discard;
+        }
+
+        // 近距离范围以外的点直接认为有效
+        if point.distance > self.config.near_range {
+            return Confidence::Normal;
+        }
+
+        // 根据强度进行分类
+        let intensity = point.intensity as u16;
+        if intensity > self.config.confidence_high {
+            // 高置信度,直接认为有效
+            Confidence::Normal
+        } else if intensity > self.config.confidence_middle {
+            // 中等置信度,严格策略下需要进一步判断
+            if self.enable_strict_policy {
+                Confidence::Pending
+            } else {
+                Confidence::Normal
             }
+        } else if intensity > self.config.confidence_low {
+            // 低置信度,需要严格判断
+            Confidence::Pending
+        } else {
             // 置信度太低的点直接丢弃
+            Confidence::Discard
         }
+    }
 
-        // 第二步:对待定点进行分组处理
-        if !pending.is_empty() {
-            // 按角度排序
-            pending.sort_by(|a, b| a.angle.partial_cmp(&b.angle).unwrap());
-
-            // 分组处理
-            for point in pending {
-                if item.is_empty() {
-                    item.push(point).ok();
-                    continue;
-                }
+    /// 根据当前转速和配置重新计算分组的角度差阈值
+    fn update_max_diff(&mut self) {
+        #[cfg(not(feature = "fixed-point"))]
+        {
+            self.max_diff = angle::from_degrees(
+                self.curr_speed / self.config.scan_freq as f32 * self.config.gap_multiplier,
+            );
+        }
+        #[cfg(feature = "fixed-point")]
+        {
+            let diff = self.curr_speed as u64 * self.gap_multiplier as u64
+                / self.config.scan_freq as u64;
+            self.max_diff = ((diff + (1 << 15)) >> 16).min(u16::MAX as u64) as u16;
+        }
+    }
 
-                // 计算角度差
-                let mut angle_diff = (point.angle - item.last().unwrap().angle).abs();
-                if angle_diff > (360.0 - point.angle + item.last().unwrap().angle) {
-                    angle_diff = 360.0 - point.angle + item.last().unwrap().angle;
-                }
+    /// 使用已检查过的配置,定点表示下换算小数系数
+    fn apply_config(&mut self, config: SlbfConfig) {
+        #[cfg(feature = "fixed-point")]
+        {
+            self.speed_smoothing = (config.speed_smoothing * Q16 + 0.5) as u32;
+            self.gap_multiplier = (config.gap_multiplier * Q16 + 0.5) as u32;
+        }
+        self.config = config;
+        self.update_max_diff();
+    }
 
-                // 角度差小于阈值,加入同一组
-                if angle_diff <= (self.curr_speed / self.config.scan_freq as f32 * 2.0) {
-                    item.push(point).ok();
-                } else {
-                    // 角度差大于阈值,开始新的一组
-                    if item.len() >= 3 {
-                        // 当前组至少3个点才认为有效
-                        for p in item.iter() {
-                            group.push(p.clone()).ok();
-                        }
-                    }
-                    item.clear();
-                    item.push(point).ok();
-                }
+    /// 将按角度排序的点中的待定点分组,点数不足的组将距离置0
+    ///
+    /// 一圈数据首尾相接,跨越0度的组在排序后被分成首尾两段,两段角度相邻时合并判断
+    fn mark_small_groups(&self, points: &mut [PointData]) {
+        let min_size = self.config.min_group_size;
+        // 第一组的结束位置和点数,等到最后一组确定后再判断
+        let mut first_group = None;
+        let mut first_angle = None;
+        let mut group_start = 0;
+        let mut group_len = 0;
+        let mut last_angle = None;
+        for i in 0..points.len() {
+            if self.classify(&points[i]) != Confidence::Pending {
+                continue;
             }
 
-            // 处理最后一组
-            if item.len() >= 3 {
-                for p in item.iter() {
-                    group.push(p.clone()).ok();
+            // 角度差大于阈值,开始新的一组
+            let current = points[i].angle;
+            if last_angle.is_some_and(|last| angle::separation(current, last) > self.max_diff) {
+                if first_group.is_none() {
+                    first_group = Some((i, group_len));
+                } else if group_len < min_size {
+                    self.mark_pending(&mut points[group_start..i]);
                 }
+                group_start = i;
+                group_len = 0;
             }
+            first_angle.get_or_insert(current);
+            group_len += 1;
+            last_angle = Some(current);
         }
 
-        // 第三步:将有效点合并
-        for point in group {
-            normal.push(point).ok();
+        // 处理第一组和最后一组
+        let Some((first_end, first_len)) = first_group else {
+            // 只有一组
+            if group_len < min_size {
+                self.mark_pending(points);
+            }
+            return;
+        };
+        let wraps = match (first_angle, last_angle) {
+            (Some(first), Some(last)) => angle::separation(first, last) <= self.max_diff,
+            _ => false,
+        };
+        if wraps {
+            if first_len + group_len < min_size {
+                self.mark_pending(&mut points[..first_end]);
+                self.mark_pending(&mut points[group_start..]);
+            }
+        } else {
+            if first_len < min_size {
+                self.mark_pending(&mut points[..first_end]);
+            }
+            if group_len < min_size {
+                self.mark_pending(&mut points[group_start..]);
+            }
         }
+    }
 
-        // 按角度排序
-        normal.sort_by(|a, b| a.angle.partial_cmp(&b.angle).unwrap());
-
-        normal
+    /// 将待定点的距离置0
+    fn mark_pending(&self, points: &mut [PointData]) {
+        for point in points {
+            if self.classify(point) == Confidence::Pending {
+                point.distance = 0;
+            }
+        }
     }
 
     /// 设置是否启用严格过滤策略
     pub fn set_strict_policy(&mut self, enable: bool) {
         self.enable_strict_policy = enable;
     }
+
+    /// 设置滤波参数,例如使用 [`LidarModel::slbf_config`] 得到的型号默认配置
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ConfigError`],原配置保持不变
+    pub fn set_config(&mut self, config: SlbfConfig) -> Result<(), ConfigError> {
+        config.validate()?;
+        self.apply_config(config);
+        Ok(())
+    }
+}
+
+/// 更新转速后对扫描数据进行近距离滤波
+impl<const N: usize> ScanFilter<N> for Slbf {
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.update_speed(scan.speed);
+        self.near_filter_in_place(&mut scan.points);
+    }
+}
+
+/// 转速(度/秒)换算为内部表示
+fn to_speed(speed: f32) -> Speed {
+    #[cfg(not(feature = "fixed-point"))]
+    {
+        speed
+    }
+    #[cfg(feature = "fixed-point")]
+    {
+        (speed * 100.0 + 0.5) as u32
+    }
+}
+
+/// 按角度比较
+fn angle_less(a: &PointData, b: &PointData) -> bool {
+    angle::cmp(a.angle, b.angle) == Ordering::Less
+}
+
+/// 开头一段按角度升序排列的点数
+fn run_len(points: &[PointData]) -> usize {
+    if points.is_empty() {
+        return 0;
+    }
+    1 + points
+        .windows(2)
+        .take_while(|pair| !angle_less(&pair[1], &pair[0]))
+        .count()
+}
+
+/// 按角度原地稳定排序
+///
+/// 将输入看作若干段升序数据逐段归并。一圈数据通常只有跨越0度处的一段,
+/// 此时只需一次旋转
+fn sort_by_angle(points: &mut [PointData]) {
+    let mut sorted = run_len(points);
+    while sorted < points.len() {
+        let run = run_len(&points[sorted..]);
+        merge(&mut points[..sorted + run], sorted);
+        sorted += run;
+    }
+}
+
+/// 原地归并 `points[..mid]` 和 `points[mid..]` 两段升序数据
+fn merge(points: &mut [PointData], mut mid: usize) {
+    let mut start = 0;
+    while start < mid && mid < points.len() {
+        // 左段中跳过不大于右段第一个点的部分
+        start += points[start..mid].partition_point(|p| !angle_less(&points[mid], p));
+        if start == mid {
+            break;
+        }
+        // 右段中小于左段当前点的部分整体移到左段当前点之前
+        let count = points[mid..].partition_point(|p| angle_less(p, &points[start]));
+        points[start..mid + count].rotate_left(mid - start);
+        start += count;
+        mid += count;
+    }
 }
 
 #[cfg(test)]
@@ -171,13 +476,13 @@ mod tests {
         // 创建测试数据
         let mut points = Vec::<PointData, 16>::new();
         points.push(PointData {
-            angle: 0.0,
+            angle: angle::from_degrees(0.0),
             distance: 500,
             intensity: 220, // 高置信度
-            stamp: 0,
+            timestamp: 0,
         }).unwrap();
         
-        let filtered = filter.near_filter(&points);
+        let filtered = filter.near_filter::<16>(&points);
         assert_eq!(filtered.len(), 1);
     }
 
@@ -188,13 +493,212 @@ mod tests {
         // 创建低置信度测试数据
         let mut points = Vec::<PointData, 16>::new();
         points.push(PointData {
-            angle: 0.0,
+            angle: angle::from_degrees(0.0),
             distance: 500,
             intensity: 80, // 低置信度
-            stamp: 0,
+            timestamp: 0,
         }).unwrap();
         
-        let filtered = filter.near_filter(&points);
+        let filtered = filter.near_filter::<16>(&points);
         assert_eq!(filtered.len(), 0); // 应该被过滤掉
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_full_revolution_is_not_truncated() {
+        let filter = Slbf::new(3600.0, false);
+
+        // LD06一圈450个点,均在近距离范围以外
+        let mut points = Vec::<PointData, 450>::new();
+        for i in 0..450 {
+            points.push(PointData {
+                angle: angle::from_centidegrees(i * 80),
+                distance: 2000,
+                intensity: 200,
+                timestamp: 0,
+            }).unwrap();
+        }
+        assert_eq!(filter.near_filter::<450>(&points).len(), 450);
+    }
+
+    #[test]
+    fn test_invalid_angle_is_dropped() {
+        let filter = Slbf::new(10.0, true);
+
+        let mut points = Vec::<PointData, 16>::new();
+        for centidegrees in [100, 40000, 200] {
+            points.push(PointData {
+                angle: angle::from_centidegrees(centidegrees),
+                distance: 1500,
+                intensity: 220,
+                timestamp: 0,
+            }).unwrap();
+        }
+        #[cfg(not(feature = "fixed-point"))]
+        points.push(PointData {
+            angle: f32::NAN,
+            distance: 1500,
+            intensity: 220,
+            timestamp: 0,
+        }).unwrap();
+
+        let invalid = filter.near_filter_in_place(&mut points);
+        assert_eq!(invalid, if cfg!(feature = "fixed-point") { 1 } else { 2 });
+        assert_eq!(points.len(), 2);
+    }
+
+    #[test]
+    fn test_sorted_runs_are_merged() {
+        let filter = Slbf::new(3600.0, false);
+
+        // 从180度开始的一圈,跨越0度后角度从头开始
+        let mut points = Vec::<PointData, 512>::new();
+        for i in 0..450 {
+            points.push(PointData {
+                angle: angle::from_centidegrees(((18000 + i * 80) % 36000) as u16),
+                distance: 1500,
+                intensity: 220,
+                timestamp: i as u64,
+            }).unwrap();
+        }
+
+        assert_eq!(filter.near_filter_in_place(&mut points), 0);
+        assert_eq!(points.len(), 450);
+        assert!(points.windows(2).all(|pair| angle_less(&pair[0], &pair[1])));
+        assert_eq!(points[0].timestamp, 225);
+        assert_eq!(points[449].timestamp, 224);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = SlbfConfig {
+            confidence_low: 210,
+            ..SlbfConfig::default()
+        };
+        assert_eq!(Slbf::with_config(10.0, true, config).err(), Some(ConfigError::ConfidenceOrder));
+
+        let config = SlbfConfig {
+            min_group_size: 0,
+            ..SlbfConfig::default()
+        };
+        assert_eq!(Slbf::with_config(10.0, true, config).err(), Some(ConfigError::ZeroGroupSize));
+
+        let mut filter = Slbf::new(10.0, true);
+        let config = SlbfConfig {
+            gap_multiplier: f32::NAN,
+            ..SlbfConfig::default()
+        };
+        assert_eq!(filter.set_config(config), Err(ConfigError::GapMultiplier));
+        assert_eq!(filter.config.gap_multiplier, 2.0);
+    }
+
+    #[test]
+    fn test_near_range_and_group_size() {
+        // 1200mm处两个低置信度点组成的小组
+        let mut points = Vec::<PointData, 16>::new();
+        for i in 0..2 {
+            points.push(PointData {
+                angle: angle::from_centidegrees(1000 + i * 80),
+                distance: 1200,
+                intensity: 100,
+                timestamp: 0,
+            }).unwrap();
+        }
+
+        // 默认配置下超出近距离范围,直接保留
+        let filter = Slbf::new(3600.0, true);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 2);
+
+        // 扩大近距离范围后,点数不足的组被丢弃
+        let config = SlbfConfig {
+            near_range: 1500,
+            ..SlbfConfig::default()
+        };
+        let filter = Slbf::with_config(3600.0, true, config.clone()).unwrap();
+        assert_eq!(filter.near_filter::<16>(&points).len(), 0);
+
+        // 降低最少点数后保留
+        let config = SlbfConfig {
+            min_group_size: 2,
+            ..config
+        };
+        let filter = Slbf::with_config(3600.0, true, config).unwrap();
+        assert_eq!(filter.near_filter::<16>(&points).len(), 2);
+    }
+
+    #[test]
+    fn test_speed_tracking() {
+        // 间隔1.5度的3个低置信度点
+        let mut points = Vec::<PointData, 16>::new();
+        for i in 0..3 {
+            points.push(PointData {
+                angle: angle::from_centidegrees(1000 + i * 150),
+                distance: 500,
+                intensity: 100,
+                timestamp: 0,
+            }).unwrap();
+        }
+
+        // 10Hz时阈值为1.6度,3个点成组保留
+        let mut filter = Slbf::new(3600.0, true);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 3);
+
+        // 降到8Hz后阈值变为1.28度,平滑后逐渐收敛
+        filter.update_speed(2880);
+        assert!((filter.speed() - 3456.0).abs() < 1e-3);
+        for _ in 0..50 {
+            filter.update_speed(2880);
+        }
+        assert!((filter.speed() - 2880.0).abs() < 1.0);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 0);
+
+        // 转速为0的测量值被忽略
+        let speed = filter.speed();
+        filter.update_speed(0);
+        assert_eq!(filter.speed(), speed);
+    }
+
+    /// 在给定角度(0.01度)处生成低置信度的近距离点
+    fn pending_points(centidegrees: &[u16]) -> Vec<PointData, 16> {
+        let mut points = Vec::new();
+        for &c in centidegrees {
+            points.push(PointData {
+                angle: angle::from_centidegrees(c),
+                distance: 400,
+                intensity: 100,
+                timestamp: 0,
+            }).unwrap();
+        }
+        points
+    }
+
+    #[test]
+    fn test_group_across_zero_heading() {
+        let filter = Slbf::new(3600.0, true);
+
+        // 正前方的物体:0度两侧各有点,分开计算时每段都不足3个点
+        let points = pending_points(&[35840, 35920, 0, 80]);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 4);
+
+        let points = pending_points(&[35920, 0, 80]);
+        let filtered = filter.near_filter::<16>(&points);
+        assert_eq!(filtered.len(), 3);
+        assert_eq!(angle::to_degrees(filtered[0].angle), 0.0);
+
+        // 物体正好在0度,另一侧还有一个孤立的组
+        let points = pending_points(&[35920, 0, 18000, 18080]);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 0);
+    }
+
+    #[test]
+    fn test_groups_near_zero_not_adjacent() {
+        let filter = Slbf::new(3600.0, true);
+
+        // 首尾两段相距超过阈值,各自点数不足,都被丢弃
+        let points = pending_points(&[100, 180, 35500, 35580]);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 0);
+
+        // 首尾合并后仍不足3个点
+        let points = pending_points(&[0, 35920, 9000, 9080, 9160]);
+        assert_eq!(filter.near_filter::<16>(&points).len(), 3);
+    }
+}
@@ -0,0 +1,239 @@
+//! 时间域平滑
+//!
+//! 将一圈按角度划分为 `BINS` 个区间,每个区间分别对最近几圈的距离做指数平滑或取中值。
+//! 新测量值与平滑结果相差过大时认为物体发生了移动,丢弃该区间的历史数据
+
+use heapless::Vec;
+
+use super::ScanFilter;
+use crate::angle;
+use crate::filter::slbf::PointData;
+use crate::scan::Scan;
+
+/// 平滑方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TemporalMode {
+    /// 指数平滑,系数取值 `(0, 1]`,越大越跟随最新的测量值
+    Exponential(f32),
+    /// 取最近 `DEPTH` 圈的中值
+    Median,
+}
+
+/// 时间域平滑配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TemporalConfig {
+    /// 平滑方式
+    pub mode: TemporalMode,
+    /// 新测量值与平滑结果的差超过该值(mm)时丢弃历史数据
+    pub reset_threshold: u16,
+}
+
+impl Default for TemporalConfig {
+    fn default() -> Self {
+        Self {
+            mode: TemporalMode::Exponential(0.3),
+            reset_threshold: 150,
+        }
+    }
+}
+
+/// 时间域平滑配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TemporalConfigError {
+    /// 指数平滑系数不在 `(0, 1]` 范围内
+    Smoothing,
+    /// 区间数或历史深度为0,或历史深度超过255
+    Capacity,
+}
+
+impl TemporalConfig {
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), TemporalConfigError> {
+        if let TemporalMode::Exponential(alpha) = self.mode {
+            if !(alpha > 0.0 && alpha <= 1.0) {
+                return Err(TemporalConfigError::Smoothing);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 时间域平滑滤波器
+///
+/// `BINS` 为一圈的角度区间数,应不少于每圈的点数,否则同一圈的相邻点会落入同一区间;
+/// `DEPTH` 为中值方式保存的历史圈数,指数平滑方式只使用1个
+pub struct TemporalFilter<const BINS: usize, const DEPTH: usize> {
+    /// 配置参数
+    config: TemporalConfig,
+    /// 指数平滑系数,以1/256为单位
+    weight: u32,
+    /// 每个区间的历史距离
+    history: [[u16; DEPTH]; BINS],
+    /// 每个区间的历史数据个数
+    len: [u8; BINS],
+    /// 每个区间下一个写入位置
+    next: [u8; BINS],
+}
+
+impl<const BINS: usize, const DEPTH: usize> TemporalFilter<BINS, DEPTH> {
+    /// 创建新的滤波器实例
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`TemporalConfigError`]
+    pub fn new(config: TemporalConfig) -> Result<Self, TemporalConfigError> {
+        if BINS == 0 || DEPTH == 0 || DEPTH > u8::MAX as usize {
+            return Err(TemporalConfigError::Capacity);
+        }
+        config.validate()?;
+        let weight = match config.mode {
+            TemporalMode::Exponential(alpha) => (alpha * 256.0 + 0.5) as u32,
+            TemporalMode::Median => 256,
+        };
+        Ok(Self {
+            config,
+            weight,
+            history: [[0; DEPTH]; BINS],
+            len: [0; BINS],
+            next: [0; BINS],
+        })
+    }
+
+    /// 丢弃所有历史数据
+    pub fn reset(&mut self) {
+        self.len = [0; BINS];
+        self.next = [0; BINS];
+    }
+
+    /// 原地滤波
+    ///
+    /// 距离为0的无效点保持不变,也不更新历史数据
+    pub fn filter_in_place(&mut self, points: &mut [PointData]) {
+        for point in points {
+            if point.distance != 0 {
+                let bin = angle::to_centidegrees(point.angle) as usize * BINS / 36000;
+                point.distance = self.update(bin.min(BINS - 1), point.distance);
+            }
+        }
+    }
+
+    /// 向区间输入一个测量值,返回平滑后的距离
+    fn update(&mut self, bin: usize, distance: u16) -> u16 {
+        let len = self.len[bin] as usize;
+        let current = match self.config.mode {
+            TemporalMode::Exponential(_) => self.history[bin][0],
+            TemporalMode::Median => median(&self.history[bin][..len]),
+        };
+
+        // 物体移动,丢弃历史数据
+        if len == 0 || current.abs_diff(distance) > self.config.reset_threshold {
+            self.history[bin][0] = distance;
+            self.len[bin] = 1;
+            self.next[bin] = 1 % DEPTH as u8;
+            return distance;
+        }
+
+        match self.config.mode {
+            TemporalMode::Exponential(_) => {
+                let smoothed =
+                    (current as u32 * (256 - self.weight) + distance as u32 * self.weight + 128)
+                        / 256;
+                self.history[bin][0] = smoothed as u16;
+                smoothed as u16
+            }
+            TemporalMode::Median => {
+                let next = self.next[bin] as usize;
+                self.history[bin][next] = distance;
+                self.next[bin] = ((next + 1) % DEPTH) as u8;
+                self.len[bin] = (len + 1).min(DEPTH) as u8;
+                median(&self.history[bin][..self.len[bin] as usize])
+            }
+        }
+    }
+}
+
+impl<const N: usize, const BINS: usize, const DEPTH: usize> ScanFilter<N>
+    for TemporalFilter<BINS, DEPTH>
+{
+    fn apply(&mut self, scan: &mut Scan<N>) {
+        self.filter_in_place(&mut scan.points);
+    }
+}
+
+/// 求中值,偶数个时取较大的一个
+fn median(values: &[u16]) -> u16 {
+    let mut sorted = Vec::<u16, 256>::new();
+    sorted.extend_from_slice(values).ok();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 单个点的一圈
+    fn point(distance: u16) -> [PointData; 1] {
+        [PointData {
+            angle: angle::from_degrees(90.0),
+            distance,
+            intensity: 200,
+            timestamp: 0,
+        }]
+    }
+
+    fn run<const DEPTH: usize>(
+        filter: &mut TemporalFilter<360, DEPTH>,
+        input: &[u16],
+    ) -> std::vec::Vec<u16> {
+        input
+            .iter()
+            .map(|&distance| {
+                let mut scan = point(distance);
+                filter.filter_in_place(&mut scan);
+                scan[0].distance
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exponential_with_motion_reset() {
+        let config = TemporalConfig {
+            mode: TemporalMode::Exponential(0.5),
+            reset_threshold: 100,
+        };
+        let mut filter = TemporalFilter::<360, 1>::new(config).unwrap();
+        // 抖动被平滑,物体靠近500mm后立即跟随
+        let output = run(&mut filter, &[1000, 1020, 980, 1000, 500, 510]);
+        assert_eq!(output, [1000, 1010, 995, 998, 500, 505]);
+    }
+
+    #[test]
+    fn test_median_over_revolutions() {
+        let config = TemporalConfig {
+            mode: TemporalMode::Median,
+            reset_threshold: 100,
+        };
+        let mut filter = TemporalFilter::<360, 3>::new(config).unwrap();
+        let output = run(&mut filter, &[1000, 1060, 990, 1010, 0, 1000, 1500]);
+        assert_eq!(output, [1000, 1060, 1000, 1010, 0, 1000, 1500]);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = TemporalConfig {
+            mode: TemporalMode::Exponential(1.5),
+            ..TemporalConfig::default()
+        };
+        assert_eq!(
+            TemporalFilter::<360, 1>::new(config).err(),
+            Some(TemporalConfigError::Smoothing)
+        );
+        assert_eq!(
+            TemporalFilter::<360, 0>::new(TemporalConfig::default()).err(),
+            Some(TemporalConfigError::Capacity)
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! 主机端雷达驱动
+//!
+//! 在后台线程中读取数据源、解析数据包、填写时间戳并拼接成完整的一圈。
+//! 用法与C++ SDK的 `LDLidarDriver::GetLaserScanData` 相同:调用
+//! [`LidarDriver::get_laser_scan_data`] 阻塞等待最新一圈,也可以在异步代码中等待
+//! [`LidarDriver::next_scan`],或用 [`LidarDriver::with_callback`] 在后台线程中处理每一圈
+
+use std::boxed::Box;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read};
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_os = "linux")]
+use super::serial::SerialPort;
+use crate::filter::slbf::Slbf;
+use crate::model::LidarModel;
+use crate::reader::{LidarReader, ReadError, StdReader};
+use crate::scan::{Scan, ScanAssembler, ScanEvent};
+use crate::timing::Timestamper;
+
+/// 单圈最大点数,足够容纳STL27L在低转速下的一圈
+pub const SCAN_CAPACITY: usize = 4096;
+
+/// 驱动输出的一圈扫描数据,时间戳为主机UNIX时间的微秒数
+pub type HostScan = Scan<SCAN_CAPACITY>;
+
+/// 停止驱动时等待后台线程结束的最长时间
+pub const STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 驱动配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverConfig {
+    /// 雷达型号
+    pub model: LidarModel,
+    /// 是否对每一圈运行近距离滤波
+    pub filter: bool,
+    /// 近距离滤波是否使用严格策略
+    pub strict_policy: bool,
+}
+
+impl Default for DriverConfig {
+    fn default() -> Self {
+        Self::with_model(LidarModel::DEFAULT)
+    }
+}
+
+impl DriverConfig {
+    /// 指定型号的默认配置,启用近距离滤波
+    pub fn with_model(model: LidarModel) -> Self {
+        Self {
+            model,
+            filter: true,
+            strict_policy: false,
+        }
+    }
+}
+
+/// 驱动错误
+#[derive(Debug)]
+pub enum DriverError {
+    /// 超时时间内没有完成新的一圈
+    Timeout,
+    /// 后台线程已结束,例如数据源已读完或驱动已停止
+    Stopped,
+    /// 数据源错误,后台线程随之结束
+    Io(io::Error),
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out waiting for a scan"),
+            Self::Stopped => write!(f, "driver stopped"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DriverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// 后台线程与调用方共享的状态
+#[derive(Default)]
+struct State {
+    /// 尚未取走的最新一圈
+    latest: Option<Box<HostScan>>,
+    /// 尚未报告的数据源错误
+    error: Option<io::Error>,
+    /// 后台线程是否已结束
+    finished: bool,
+    /// 等待下一圈的异步任务
+    waker: Option<Waker>,
+}
+
+/// 共享状态及其条件变量
+#[derive(Default)]
+struct Shared {
+    /// 共享状态
+    state: Mutex<State>,
+    /// 有新的一圈或后台线程结束时通知
+    ready: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// 修改状态并唤醒所有等待方
+    fn notify(&self, update: impl FnOnce(&mut State)) {
+        let mut state = self.lock();
+        update(&mut state);
+        let waker = state.waker.take();
+        drop(state);
+        self.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// 取走最新一圈,没有时返回 `None`,后台线程已结束时返回错误
+    fn take(state: &mut State) -> Option<Result<Box<HostScan>, DriverError>> {
+        if let Some(scan) = state.latest.take() {
+            return Some(Ok(scan));
+        }
+        if let Some(err) = state.error.take() {
+            return Some(Err(DriverError::Io(err)));
+        }
+        state.finished.then_some(Err(DriverError::Stopped))
+    }
+}
+
+/// 主机端雷达驱动
+///
+/// 被丢弃时停止后台线程。后台线程只在两次读取之间检查停止标志,
+/// 数据源应在没有数据时定期返回(例如设置了读取超时的串口),
+/// 否则停止时最多等待 [`STOP_TIMEOUT`],之后不再等待阻塞在读取中的后台线程
+pub struct LidarDriver {
+    /// 共享状态
+    shared: Arc<Shared>,
+    /// 通知后台线程停止
+    stop: Arc<AtomicBool>,
+    /// 后台线程
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LidarDriver {
+    /// 打开串口并启动驱动
+    #[cfg(target_os = "linux")]
+    pub fn open(path: impl AsRef<Path>, config: DriverConfig) -> io::Result<Self> {
+        let port = SerialPort::open_model(path, config.model)?;
+        Self::start(port, config)
+    }
+
+    /// 从任意数据源启动驱动,例如串口、伪终端或录制文件
+    ///
+    /// 数据源的读取返回 [`io::ErrorKind::TimedOut`] 或 [`io::ErrorKind::WouldBlock`]
+    /// 时继续等待,读完时后台线程结束。管道、套接字和伪终端主设备的读取没有超时,
+    /// 伪终端应通过 [`SerialPort::open`] 打开从设备,见 [`LidarDriver`] 的说明
+    pub fn start<R: Read + Send + 'static>(io: R, config: DriverConfig) -> io::Result<Self> {
+        let shared = Arc::new(Shared::default());
+        let sink = shared.clone();
+        Self::spawn(io, config, shared, move |scan: &HostScan| {
+            let scan = Box::new(scan.clone());
+            sink.notify(|state| state.latest = Some(scan));
+        })
+    }
+
+    /// 从任意数据源启动驱动,每完成一圈在后台线程中调用一次 `callback`
+    ///
+    /// 此时 [`Self::get_laser_scan_data`] 和 [`Self::next_scan`] 只报告错误和结束
+    pub fn with_callback<R, F>(io: R, config: DriverConfig, callback: F) -> io::Result<Self>
+    where
+        R: Read + Send + 'static,
+        F: FnMut(&HostScan) + Send + 'static,
+    {
+        Self::spawn(io, config, Arc::new(Shared::default()), callback)
+    }
+
+    /// 等待最新的一圈扫描数据
+    ///
+    /// 上一次调用之后已经完成的一圈立即返回,多圈未取走时只保留最新的一圈
+    pub fn get_laser_scan_data(&self, timeout: Duration) -> Result<Box<HostScan>, DriverError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(result) = Shared::take(&mut state) {
+                return result;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(DriverError::Timeout);
+            }
+            state = self
+                .shared
+                .ready
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+    }
+
+    /// 异步等待最新的一圈扫描数据,不依赖特定的异步运行时
+    pub fn next_scan(&self) -> NextScan<'_> {
+        NextScan { driver: self }
+    }
+
+    /// 后台线程是否仍在运行
+    pub fn is_running(&self) -> bool {
+        !self.shared.lock().finished
+    }
+
+    /// 停止后台线程并等待其结束
+    ///
+    /// 后台线程在 [`STOP_TIMEOUT`] 内没有结束时(阻塞在没有超时的读取中)不再等待,
+    /// 它会在读取返回后自行结束,不再调用回调
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        let mut state = self.shared.lock();
+        while !state.finished {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return;
+            }
+            state = self
+                .shared
+                .ready
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+        drop(state);
+        thread.join().ok();
+    }
+
+    /// 启动后台线程
+    fn spawn<R, F>(io: R, config: DriverConfig, shared: Arc<Shared>, on_scan: F) -> io::Result<Self>
+    where
+        R: Read + Send + 'static,
+        F: FnMut(&HostScan) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            shared: shared.clone(),
+            stop: stop.clone(),
+        };
+        let thread = thread::Builder::new()
+            .name("ldlidar".into())
+            .spawn(move || worker.run(io, config, on_scan))?;
+        Ok(Self {
+            shared,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for LidarDriver {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// [`LidarDriver::next_scan`] 返回的Future
+pub struct NextScan<'a> {
+    /// 驱动
+    driver: &'a LidarDriver,
+}
+
+impl Future for NextScan<'_> {
+    type Output = Result<Box<HostScan>, DriverError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.driver.shared.lock();
+        match Shared::take(&mut state) {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// 后台线程
+struct Worker {
+    /// 共享状态
+    shared: Arc<Shared>,
+    /// 停止标志
+    stop: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run<R: Read>(self, io: R, config: DriverConfig, mut on_scan: impl FnMut(&HostScan)) {
+        let model = config.model;
+        let mut reader = LidarReader::with_model(StdReader(io), model);
+        let mut assembler = ScanAssembler::<SCAN_CAPACITY>::with_model(model);
+        let mut timestamper = Timestamper::with_model(model);
+        let mut filter = Slbf::new(model.scan_freq() as f32 * 360.0, config.strict_policy);
+        filter.set_config(model.slbf_config()).ok();
+
+        let mut error = None;
+        while !self.stop.load(Ordering::Relaxed) {
+            let mut packet = match reader.read_packet_blocking() {
+                Ok(packet) => packet,
+                Err(ReadError::Parse(_)) => continue,
+                Err(ReadError::Io(err))
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    continue
+                }
+                Err(ReadError::Io(err)) => {
+                    error = Some(err);
+                    break;
+                }
+                Err(ReadError::Eof) => break,
+            };
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            timestamper.stamp(&mut packet, now());
+            filter.update_speed(packet.speed);
+            for point in packet.points {
+                if let Some(ScanEvent::Complete(scan)) = assembler.push(point, packet.speed) {
+                    if config.filter {
+                        filter.near_filter_in_place(&mut scan.points);
+                    }
+                    on_scan(scan);
+                }
+            }
+        }
+
+        self.shared.notify(|state| {
+            state.error = error;
+            state.finished = true;
+        });
+    }
+}
+
+/// 主机UNIX时间的微秒数
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
@@ -0,0 +1,10 @@
+//! 主机端支持
+//!
+//! 在Linux上直接连接雷达时使用:[`serial`] 通过termios打开串口或伪终端,
+//! [`driver`] 在后台线程中完成解析和拼接,并提供阻塞、异步和回调三种接收方式
+
+pub mod driver;
+#[cfg(target_os = "linux")]
+pub mod serial;
+
+pub use driver::{DriverConfig, DriverError, HostScan, LidarDriver};
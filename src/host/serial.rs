@@ -0,0 +1,151 @@
+//! Linux串口
+//!
+//! 直接用termios把 `/dev/ttyUSB*` 等设备配置为原始模式,不依赖其他串口库。
+//! 同时提供伪终端,便于在没有硬件时用模拟数据测试
+
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::model::LidarModel;
+
+/// 默认的读取超时
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// 串口
+///
+/// 读取时超过超时时间没有收到数据返回 [`io::ErrorKind::TimedOut`]
+pub struct SerialPort {
+    /// 设备文件
+    file: File,
+}
+
+impl SerialPort {
+    /// 打开串口,配置为原始模式、8N1、无流控
+    ///
+    /// # Arguments
+    /// * `path` - 设备路径,例如 `/dev/ttyUSB0`
+    /// * `baud_rate` - 波特率
+    /// * `timeout` - 读取超时,以0.1秒为单位向上取整,最长25.5秒
+    pub fn open(path: impl AsRef<Path>, baud_rate: u32, timeout: Duration) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)?;
+        configure(file.as_raw_fd(), baud_rate, timeout)?;
+        Ok(Self { file })
+    }
+
+    /// 以指定型号的波特率打开串口,使用默认的读取超时
+    pub fn open_model(path: impl AsRef<Path>, model: LidarModel) -> io::Result<Self> {
+        Self::open(path, model.baud_rate(), DEFAULT_TIMEOUT)
+    }
+}
+
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.file.read(buf)? {
+            0 if !buf.is_empty() => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "serial read timed out",
+            )),
+            len => Ok(len),
+        }
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl AsRawFd for SerialPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// 打开一对伪终端
+///
+/// # Returns
+/// * 主设备,写入的数据可以从从设备读出,反之亦然
+/// * 从设备路径,可以像真实串口一样用 [`SerialPort::open`] 打开
+pub fn open_pty() -> io::Result<(File, PathBuf)> {
+    // SAFETY: posix_openpt返回新的文件描述符,由File接管
+    let master = unsafe {
+        let fd = check(libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY))?;
+        File::from_raw_fd(fd)
+    };
+    let fd = master.as_raw_fd();
+    let mut name = [0 as libc::c_char; 128];
+    // SAFETY: fd有效,name的长度与传入的长度一致
+    unsafe {
+        check(libc::grantpt(fd))?;
+        check(libc::unlockpt(fd))?;
+        let err = libc::ptsname_r(fd, name.as_mut_ptr(), name.len());
+        if err != 0 {
+            return Err(io::Error::from_raw_os_error(err));
+        }
+    }
+    // SAFETY: ptsname_r成功时写入以0结尾的字符串
+    let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+    Ok((master, PathBuf::from(name.to_string_lossy().into_owned())))
+}
+
+/// 配置termios
+fn configure(fd: RawFd, baud_rate: u32, timeout: Duration) -> io::Result<()> {
+    let speed = speed(baud_rate)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unsupported baud rate"))?;
+    let deciseconds = timeout.as_millis().div_ceil(100).clamp(1, 255) as libc::cc_t;
+
+    // SAFETY: termios是普通的C结构体,全0是合法的初始值,随后由tcgetattr填充
+    unsafe {
+        let mut tio: libc::termios = core::mem::zeroed();
+        check(libc::tcgetattr(fd, &mut tio))?;
+        libc::cfmakeraw(&mut tio);
+        tio.c_cflag |= libc::CLOCAL | libc::CREAD;
+        tio.c_cflag &= !(libc::CSTOPB | libc::PARENB | libc::CRTSCTS);
+        // 有数据时立即返回,没有数据时最多等待超时时间
+        tio.c_cc[libc::VMIN] = 0;
+        tio.c_cc[libc::VTIME] = deciseconds;
+        check(libc::cfsetispeed(&mut tio, speed))?;
+        check(libc::cfsetospeed(&mut tio, speed))?;
+        check(libc::tcsetattr(fd, libc::TCSANOW, &tio))?;
+        check(libc::tcflush(fd, libc::TCIFLUSH))?;
+    }
+    Ok(())
+}
+
+/// 波特率对应的termios常量
+fn speed(baud_rate: u32) -> Option<libc::speed_t> {
+    Some(match baud_rate {
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        460800 => libc::B460800,
+        921600 => libc::B921600,
+        _ => return None,
+    })
+}
+
+/// 将返回-1的系统调用转换为错误
+fn check(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
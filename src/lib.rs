@@ -1,2 +1,25 @@
-mod filter;
-mod serial_interface;
+#![no_std]
+
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
+pub mod angle;
+pub mod capture;
+pub mod clock_sync;
+pub mod filter;
+#[cfg(feature = "std")]
+pub mod host;
+pub mod model;
+pub mod protocol;
+pub mod reader;
+pub mod resample;
+pub mod scan;
+pub mod sim;
+#[cfg(feature = "firmware")]
+pub mod serial_interface;
+pub mod timing;
+
+pub use angle::Angle;
+pub use filter::slbf::{PointData, Slbf, SlbfConfig};
+pub use filter::{FilterChain, ScanFilter};
+pub use model::LidarModel;
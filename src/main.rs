@@ -10,19 +10,80 @@ use {defmt_rtt as _, panic_probe as _};
 
 use embassy_executor::Spawner;
 use embassy_stm32::gpio::{Level, Output, Speed};
-use embassy_time::{Duration, Timer};
-use fmt::info;
+use embassy_stm32::usart::{self, Uart};
+use embassy_stm32::{bind_interrupts, peripherals};
+use embassy_time::Instant;
+use fmt::{info, unwrap, warn};
+use ldlidar_driver::model::LidarModel;
+use ldlidar_driver::reader::LidarReader;
+use ldlidar_driver::scan::{ScanAssembler, ScanEvent};
+use ldlidar_driver::serial_interface::{self, SerialInterface};
+use ldlidar_driver::timing::Timestamper;
+use ldlidar_driver::Slbf;
+
+bind_interrupts!(struct Irqs {
+    USART1 => usart::InterruptHandler<peripherals::USART1>;
+});
+
+/// 雷达型号,由 `model-*` 特性选择
+const MODEL: LidarModel = LidarModel::DEFAULT;
+
+/// 单圈最大点数,由型号决定
+const SCAN_CAPACITY: usize = MODEL.max_points_per_revolution();
 
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
     let p = embassy_stm32::init(Default::default());
     let mut led = Output::new(p.PB7, Level::High, Speed::Low);
 
+    let config = serial_interface::config(MODEL);
+    let uart = unwrap!(Uart::new(
+        p.USART1,
+        p.PA10,
+        p.PA9,
+        Irqs,
+        p.DMA1_CH4,
+        p.DMA1_CH5,
+        config,
+    ));
+    let mut rx_buf = [0u8; 256];
+    let mut serial = SerialInterface::new(uart, &mut rx_buf, config);
+    unwrap!(serial.open());
+
+    let mut reader = LidarReader::with_model(serial, MODEL);
+    let mut assembler = ScanAssembler::<SCAN_CAPACITY>::with_model(MODEL);
+    // 以标称转速启动,之后根据测得的转速更新
+    let mut filter = Slbf::new(MODEL.scan_freq() as f32 * 360.0, false);
+    unwrap!(filter.set_config(MODEL.slbf_config()));
+    let mut timestamper = Timestamper::with_model(MODEL);
+
     loop {
-        info!("Hello, World!");
-        led.set_high();
-        Timer::after(Duration::from_millis(500)).await;
-        led.set_low();
-        Timer::after(Duration::from_millis(500)).await;
+        let mut packet = match reader.read_packet().await {
+            Ok(packet) => packet,
+            Err(err) => {
+                warn!("read error: {}", err);
+                continue;
+            }
+        };
+
+        timestamper.stamp(&mut packet, Instant::now().as_micros());
+        filter.update_speed(packet.speed);
+        for point in packet.points {
+            match assembler.push(point, packet.speed) {
+                Some(ScanEvent::Complete(scan)) => {
+                    let count = scan.len();
+                    let invalid = filter.near_filter_in_place(&mut scan.points);
+                    if invalid > 0 {
+                        warn!("dropped {} points with invalid angle", invalid);
+                    }
+                    info!("scan: {} points, {} after filter", count, scan.len());
+                    led.toggle();
+                }
+                Some(ScanEvent::Incomplete(incomplete)) => {
+                    warn!("incomplete scan: {}", incomplete);
+                }
+                None => {}
+            }
+        }
     }
 }
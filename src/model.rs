@@ -0,0 +1,279 @@
+use core::str::FromStr;
+
+use crate::angle::{self, Angle};
+use crate::filter::slbf::SlbfConfig;
+use crate::protocol::Protocol;
+
+// `model-*` 特性决定固件使用的型号,同时启用多个时无法确定用哪一个
+#[cfg(any(
+    all(
+        feature = "model-ld06",
+        any(
+            feature = "model-ld19",
+            feature = "model-stl06p",
+            feature = "model-stl26",
+            feature = "model-stl27l",
+            feature = "model-ld14",
+            feature = "model-ld14p"
+        )
+    ),
+    all(
+        feature = "model-ld19",
+        any(
+            feature = "model-stl06p",
+            feature = "model-stl26",
+            feature = "model-stl27l",
+            feature = "model-ld14",
+            feature = "model-ld14p"
+        )
+    ),
+    all(
+        feature = "model-stl06p",
+        any(
+            feature = "model-stl26",
+            feature = "model-stl27l",
+            feature = "model-ld14",
+            feature = "model-ld14p"
+        )
+    ),
+    all(
+        feature = "model-stl26",
+        any(
+            feature = "model-stl27l",
+            feature = "model-ld14",
+            feature = "model-ld14p"
+        )
+    ),
+    all(
+        feature = "model-stl27l",
+        any(feature = "model-ld14", feature = "model-ld14p")
+    ),
+    all(feature = "model-ld14", feature = "model-ld14p"),
+))]
+compile_error!("at most one model-* feature can be enabled");
+
+/// 雷达型号
+///
+/// LD06、LD19和STL系列使用0x54帧头的数据协议,LD14系列使用0xAA帧头的数据协议,
+/// 各型号的波特率、测量频率和量程各不相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LidarModel {
+    /// LD06
+    Ld06,
+    /// LD19
+    Ld19,
+    /// STL-06P
+    Stl06p,
+    /// STL-26
+    Stl26,
+    /// STL-27L
+    Stl27l,
+    /// LD14,协议解析是实验性的,见 [`crate::protocol::ld14`]
+    Ld14,
+    /// LD14P,协议解析是实验性的,见 [`crate::protocol::ld14`]
+    Ld14p,
+}
+
+impl LidarModel {
+    /// 默认型号,可通过 `model-*` 特性选择,未指定时为LD06,最多只能启用一个 `model-*` 特性
+    pub const DEFAULT: Self = if cfg!(feature = "model-ld19") {
+        Self::Ld19
+    } else if cfg!(feature = "model-stl06p") {
+        Self::Stl06p
+    } else if cfg!(feature = "model-stl26") {
+        Self::Stl26
+    } else if cfg!(feature = "model-stl27l") {
+        Self::Stl27l
+    } else if cfg!(feature = "model-ld14") {
+        Self::Ld14
+    } else if cfg!(feature = "model-ld14p") {
+        Self::Ld14p
+    } else {
+        Self::Ld06
+    };
+
+    /// 所有型号
+    pub const ALL: [Self; 7] = [
+        Self::Ld06,
+        Self::Ld19,
+        Self::Stl06p,
+        Self::Stl26,
+        Self::Stl27l,
+        Self::Ld14,
+        Self::Ld14p,
+    ];
+
+    /// 型号名称
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ld06 => "LD06",
+            Self::Ld19 => "LD19",
+            Self::Stl06p => "STL-06P",
+            Self::Stl26 => "STL-26",
+            Self::Stl27l => "STL-27L",
+            Self::Ld14 => "LD14",
+            Self::Ld14p => "LD14P",
+        }
+    }
+
+    /// 协议解析是否是实验性的,尚未用实测数据验证
+    pub const fn is_experimental(self) -> bool {
+        matches!(self, Self::Ld14 | Self::Ld14p)
+    }
+
+    /// 数据协议
+    pub const fn protocol(self) -> Protocol {
+        match self {
+            Self::Ld14 | Self::Ld14p => Protocol::Ld14,
+            _ => Protocol::Ld06,
+        }
+    }
+
+    /// 串口波特率
+    pub const fn baud_rate(self) -> u32 {
+        match self {
+            Self::Ld06 | Self::Ld19 | Self::Stl06p | Self::Stl26 | Self::Ld14p => 230400,
+            Self::Stl27l => 921600,
+            Self::Ld14 => 115200,
+        }
+    }
+
+    /// 测量频率(点/秒)
+    pub const fn sample_rate(self) -> u16 {
+        match self {
+            Self::Ld06 | Self::Ld19 | Self::Stl06p => 4500,
+            Self::Stl26 => 5000,
+            Self::Stl27l => 21600,
+            Self::Ld14 => 2300,
+            Self::Ld14p => 4000,
+        }
+    }
+
+    /// 最小有效距离(mm)
+    pub const fn min_range(self) -> u16 {
+        match self {
+            Self::Ld06 | Self::Ld19 | Self::Stl06p => 20,
+            Self::Stl26 => 50,
+            Self::Stl27l => 30,
+            Self::Ld14 | Self::Ld14p => 100,
+        }
+    }
+
+    /// 最大有效距离(mm)
+    pub const fn max_range(self) -> u16 {
+        match self {
+            Self::Ld06 | Self::Ld19 | Self::Stl06p => 12000,
+            Self::Stl26 => 26000,
+            Self::Stl27l => 25000,
+            Self::Ld14 | Self::Ld14p => 8000,
+        }
+    }
+
+    /// 标称扫描频率(Hz)
+    pub const fn scan_freq(self) -> u16 {
+        match self {
+            Self::Ld14 | Self::Ld14p => 6,
+            _ => 10,
+        }
+    }
+
+    /// 标称转速下每圈的点数
+    pub const fn points_per_revolution(self) -> usize {
+        (self.sample_rate() / self.scan_freq()) as usize
+    }
+
+    /// 每圈最多的点数,在标称点数上留出1/8的余量,允许转速低于标称值
+    ///
+    /// 可用作 [`crate::scan::ScanAssembler`] 的容量
+    pub const fn max_points_per_revolution(self) -> usize {
+        let points = self.points_per_revolution();
+        points + points / 8
+    }
+
+    /// 标称转速下相邻两点的角度差(度)
+    pub fn angle_step(self) -> f32 {
+        360.0 * self.scan_freq() as f32 / self.sample_rate() as f32
+    }
+
+    /// 扫描拼接时相邻点允许的最大角度差,用于判断是否丢失了数据包
+    pub fn max_gap(self) -> Angle {
+        angle::from_degrees(self.angle_step() * 6.0)
+    }
+
+    /// 该型号的近距离滤波器默认配置
+    pub fn slbf_config(self) -> SlbfConfig {
+        SlbfConfig {
+            scan_freq: self.sample_rate(),
+            ..SlbfConfig::default()
+        }
+    }
+}
+
+impl Default for LidarModel {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// 无法识别的型号名称
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UnknownModel;
+
+impl FromStr for LidarModel {
+    type Err = UnknownModel;
+
+    /// 按名称解析型号,忽略大小写和连字符,例如 `ld06`、`STL-27L`
+    ///
+    /// 不接受协议解析是实验性的型号,见 [`LidarModel::is_experimental`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut normalized = [0u8; 8];
+        let mut len = 0;
+        for byte in s.bytes().filter(|&b| b != b'-' && b != b'_') {
+            if len == normalized.len() {
+                return Err(UnknownModel);
+            }
+            normalized[len] = byte.to_ascii_lowercase();
+            len += 1;
+        }
+        match &normalized[..len] {
+            b"ld06" => Ok(Self::Ld06),
+            b"ld19" => Ok(Self::Ld19),
+            b"stl06p" => Ok(Self::Stl06p),
+            b"stl26" => Ok(Self::Stl26),
+            b"stl27l" => Ok(Self::Stl27l),
+            _ => Err(UnknownModel),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name() {
+        for model in LidarModel::ALL {
+            let expected = if model.is_experimental() {
+                Err(UnknownModel)
+            } else {
+                Ok(model)
+            };
+            assert_eq!(model.name().parse::<LidarModel>(), expected);
+        }
+        assert_eq!("stl_27l".parse::<LidarModel>(), Ok(LidarModel::Stl27l));
+        assert_eq!("ld99".parse::<LidarModel>(), Err(UnknownModel));
+        assert_eq!("".parse::<LidarModel>(), Err(UnknownModel));
+    }
+
+    #[test]
+    fn test_model_parameters() {
+        assert_eq!(LidarModel::Ld06.points_per_revolution(), 450);
+        assert_eq!(LidarModel::Stl27l.points_per_revolution(), 2160);
+        assert_eq!(LidarModel::Ld06.max_points_per_revolution(), 506);
+        assert_eq!(LidarModel::Stl27l.max_points_per_revolution(), 2430);
+        assert_eq!(LidarModel::Stl27l.baud_rate(), 921600);
+        assert_eq!(LidarModel::Ld19.slbf_config().scan_freq, 4500);
+    }
+}
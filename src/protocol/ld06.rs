@@ -0,0 +1,352 @@
+//! LD06/LD19 数据协议
+//!
+//! 每个数据包固定47字节,小端字节序:
+//!
+//! | 偏移 | 长度 | 内容 |
+//! | ---- | ---- | ---- |
+//! | 0 | 1 | 帧头 0x54 |
+//! | 1 | 1 | 版本/长度 0x2C |
+//! | 2 | 2 | 转速(度/秒) |
+//! | 4 | 2 | 起始角度(0.01度) |
+//! | 6 | 36 | 12个测量点,每点距离(mm,2字节)+强度(1字节) |
+//! | 42 | 2 | 结束角度(0.01度) |
+//! | 44 | 2 | 时间戳(毫秒) |
+//! | 46 | 1 | CRC8校验 |
+
+use heapless::Vec;
+
+use super::{Packet, ParseError};
+use crate::angle;
+use crate::filter::slbf::PointData;
+use crate::model::LidarModel;
+
+/// 帧头
+pub const PKG_HEADER: u8 = 0x54;
+/// 版本/长度字段
+pub const PKG_VER_LEN: u8 = 0x2C;
+/// 单个数据包中的测量点数
+pub const POINT_PER_PACK: usize = 12;
+/// 数据包长度
+pub const PACKET_LEN: usize = 47;
+
+/// LDROBOT CRC8校验表(多项式0x4D)
+const CRC_TABLE: [u8; 256] = [
+    0x00, 0x4d, 0x9a, 0xd7, 0x79, 0x34, 0xe3, 0xae, 0xf2, 0xbf, 0x68, 0x25, 0x8b, 0xc6, 0x11, 0x5c,
+    0xa9, 0xe4, 0x33, 0x7e, 0xd0, 0x9d, 0x4a, 0x07, 0x5b, 0x16, 0xc1, 0x8c, 0x22, 0x6f, 0xb8, 0xf5,
+    0x1f, 0x52, 0x85, 0xc8, 0x66, 0x2b, 0xfc, 0xb1, 0xed, 0xa0, 0x77, 0x3a, 0x94, 0xd9, 0x0e, 0x43,
+    0xb6, 0xfb, 0x2c, 0x61, 0xcf, 0x82, 0x55, 0x18, 0x44, 0x09, 0xde, 0x93, 0x3d, 0x70, 0xa7, 0xea,
+    0x3e, 0x73, 0xa4, 0xe9, 0x47, 0x0a, 0xdd, 0x90, 0xcc, 0x81, 0x56, 0x1b, 0xb5, 0xf8, 0x2f, 0x62,
+    0x97, 0xda, 0x0d, 0x40, 0xee, 0xa3, 0x74, 0x39, 0x65, 0x28, 0xff, 0xb2, 0x1c, 0x51, 0x86, 0xcb,
+    0x21, 0x6c, 0xbb, 0xf6, 0x58, 0x15, 0xc2, 0x8f, 0xd3, 0x9e, 0x49, 0x04, 0xaa, 0xe7, 0x30, 0x7d,
+    0x88, 0xc5, 0x12, 0x5f, 0xf1, 0xbc, 0x6b, 0x26, 0x7a, 0x37, 0xe0, 0xad, 0x03, 0x4e, 0x99, 0xd4,
+    0x7c, 0x31, 0xe6, 0xab, 0x05, 0x48, 0x9f, 0xd2, 0x8e, 0xc3, 0x14, 0x59, 0xf7, 0xba, 0x6d, 0x20,
+    0xd5, 0x98, 0x4f, 0x02, 0xac, 0xe1, 0x36, 0x7b, 0x27, 0x6a, 0xbd, 0xf0, 0x5e, 0x13, 0xc4, 0x89,
+    0x63, 0x2e, 0xf9, 0xb4, 0x1a, 0x57, 0x80, 0xcd, 0x91, 0xdc, 0x0b, 0x46, 0xe8, 0xa5, 0x72, 0x3f,
+    0xca, 0x87, 0x50, 0x1d, 0xb3, 0xfe, 0x29, 0x64, 0x38, 0x75, 0xa2, 0xef, 0x41, 0x0c, 0xdb, 0x96,
+    0x42, 0x0f, 0xd8, 0x95, 0x3b, 0x76, 0xa1, 0xec, 0xb0, 0xfd, 0x2a, 0x67, 0xc9, 0x84, 0x53, 0x1e,
+    0xeb, 0xa6, 0x71, 0x3c, 0x92, 0xdf, 0x08, 0x45, 0x19, 0x54, 0x83, 0xce, 0x60, 0x2d, 0xfa, 0xb7,
+    0x5d, 0x10, 0xc7, 0x8a, 0x24, 0x69, 0xbe, 0xf3, 0xaf, 0xe2, 0x35, 0x78, 0xd6, 0x9b, 0x4c, 0x01,
+    0xf4, 0xb9, 0x6e, 0x23, 0x8d, 0xc0, 0x17, 0x5a, 0x06, 0x4b, 0x9c, 0xd1, 0x7f, 0x32, 0xe5, 0xa8,
+];
+
+/// 计算CRC8校验值
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |crc, &byte| CRC_TABLE[(crc ^ byte) as usize])
+}
+
+/// 编码一个数据包,用于模拟器和测试
+///
+/// # Arguments
+/// * `speed` - 转速(度/秒)
+/// * `start` - 起始角度(0.01度)
+/// * `end` - 结束角度(0.01度)
+/// * `timestamp` - 时间戳(毫秒)
+/// * `points` - 每个点的距离(mm)和强度
+pub fn encode(
+    speed: u16,
+    start: u16,
+    end: u16,
+    timestamp: u16,
+    points: &[(u16, u8); POINT_PER_PACK],
+) -> [u8; PACKET_LEN] {
+    let mut buf = [0u8; PACKET_LEN];
+    buf[0] = PKG_HEADER;
+    buf[1] = PKG_VER_LEN;
+    buf[2..4].copy_from_slice(&speed.to_le_bytes());
+    buf[4..6].copy_from_slice(&start.to_le_bytes());
+    for (i, &(distance, intensity)) in points.iter().enumerate() {
+        let offset = 6 + i * 3;
+        buf[offset..offset + 2].copy_from_slice(&distance.to_le_bytes());
+        buf[offset + 2] = intensity;
+    }
+    buf[42..44].copy_from_slice(&end.to_le_bytes());
+    buf[44..46].copy_from_slice(&timestamp.to_le_bytes());
+    buf[46] = crc8(&buf[..46]);
+    buf
+}
+
+/// 读取小端u16
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// LD06/LD19 流式解析器
+///
+/// 逐字节输入串口数据,在帧头处同步,校验通过后输出完整的数据包
+pub struct Ld06Parser {
+    /// 雷达型号
+    model: LidarModel,
+    /// 当前帧缓冲区
+    buf: [u8; PACKET_LEN],
+    /// 缓冲区中已接收的字节数
+    len: usize,
+}
+
+impl Default for Ld06Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ld06Parser {
+    /// 创建新的解析器实例,使用默认型号
+    pub const fn new() -> Self {
+        Self::with_model(LidarModel::DEFAULT)
+    }
+
+    /// 创建指定型号的解析器实例
+    pub const fn with_model(model: LidarModel) -> Self {
+        Self {
+            model,
+            buf: [0; PACKET_LEN],
+            len: 0,
+        }
+    }
+
+    /// 丢弃已接收的不完整数据,重新等待帧头
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// 输入一个字节
+    ///
+    /// # Returns
+    /// * `None` - 数据包尚未接收完整
+    /// * `Some(Ok(packet))` - 解析出一个完整的数据包
+    /// * `Some(Err(err))` - 数据包接收完整但校验失败或内容无效
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, ParseError>> {
+        match self.len {
+            0 => {
+                if byte == PKG_HEADER {
+                    self.buf[0] = byte;
+                    self.len = 1;
+                }
+                None
+            }
+            1 => {
+                if byte == PKG_VER_LEN {
+                    self.buf[1] = byte;
+                    self.len = 2;
+                } else if byte != PKG_HEADER {
+                    self.len = 0;
+                }
+                None
+            }
+            _ => {
+                self.buf[self.len] = byte;
+                self.len += 1;
+                if self.len < PACKET_LEN {
+                    return None;
+                }
+
+                let expected = crc8(&self.buf[..PACKET_LEN - 1]);
+                let actual = self.buf[PACKET_LEN - 1];
+                if expected != actual {
+                    self.resync();
+                    return Some(Err(ParseError::Crc { expected, actual }));
+                }
+
+                self.len = 0;
+                Some(self.decode())
+            }
+        }
+    }
+
+    /// 连续输入多个字节,依次返回解析结果
+    pub fn feed<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<Packet, ParseError>> + 'a {
+        bytes.iter().filter_map(move |&byte| self.push(byte))
+    }
+
+    /// 校验失败后在已接收的数据中查找下一个帧头,避免丢失紧随其后的数据包
+    fn resync(&mut self) {
+        let start = (1..PACKET_LEN)
+            .find(|&i| {
+                self.buf[i] == PKG_HEADER && (i + 1 == PACKET_LEN || self.buf[i + 1] == PKG_VER_LEN)
+            })
+            .unwrap_or(PACKET_LEN);
+        self.buf.copy_within(start.., 0);
+        self.len = PACKET_LEN - start;
+    }
+
+    /// 解码一个已通过校验的数据包
+    fn decode(&self) -> Result<Packet, ParseError> {
+        let buf = &self.buf;
+        let speed = read_u16(buf, 2);
+        let start = read_u16(buf, 4);
+        let end = read_u16(buf, 42);
+        let timestamp = read_u16(buf, 44);
+
+        // 结束角度可能已越过0度,按顺时针方向计算角度跨度
+        let diff = (end as u32 + 36000 - start as u32) % 36000;
+
+        // 角度跨度不应明显超过当前转速下12个点所转过的角度的1.5倍,
+        // 即 diff / 100 > speed * 12 / sample_rate * 1.5
+        if diff * self.model.sample_rate() as u32 > speed as u32 * POINT_PER_PACK as u32 * 150 {
+            return Err(ParseError::AngleSpan);
+        }
+
+        let start_angle = angle::from_centidegrees(start);
+        let span = angle::from_centidegrees(diff as u16);
+        let range = self.model.min_range()..=self.model.max_range();
+
+        let mut points = Vec::new();
+        for i in 0..POINT_PER_PACK {
+            let offset = 6 + i * 3;
+            let angle = angle::interpolate(start_angle, span, i, POINT_PER_PACK - 1);
+            // 量程之外的距离视为无效测量
+            let mut distance = read_u16(buf, offset);
+            if !range.contains(&distance) {
+                distance = 0;
+            }
+            points
+                .push(PointData {
+                    angle,
+                    distance,
+                    intensity: buf[offset + 2],
+                    timestamp: 0,
+                })
+                .ok();
+        }
+
+        Ok(Packet {
+            speed,
+            start_angle,
+            end_angle: angle::from_centidegrees(end),
+            timestamp,
+            points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 转速3600度/秒,100.00度到108.80度,距离500mm起每点递增10mm
+    const FRAME: [u8; PACKET_LEN] = [
+        0x54, 0x2C, 0x10, 0x0E, 0x10, 0x27, 0xF4, 0x01, 0xC8, 0xFE, 0x01, 0xC8, 0x08, 0x02, 0xC8,
+        0x12, 0x02, 0xC8, 0x1C, 0x02, 0xC8, 0x26, 0x02, 0xC8, 0x30, 0x02, 0xC8, 0x3A, 0x02, 0xC8,
+        0x44, 0x02, 0xC8, 0x4E, 0x02, 0xC8, 0x58, 0x02, 0xC8, 0x62, 0x02, 0xC8, 0x80, 0x2A, 0x39,
+        0x30, 0x89,
+    ];
+
+    /// 355.00度到3.80度,跨越0度
+    const FRAME_WRAP: [u8; PACKET_LEN] = [
+        0x54, 0x2C, 0x10, 0x0E, 0xAC, 0x8A, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4,
+        0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4,
+        0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0xB0, 0x04, 0xB4, 0x7C, 0x01, 0x26,
+        0x75, 0x52,
+    ];
+
+    #[test]
+    fn test_crc8_table() {
+        assert_eq!(crc8(&[]), 0);
+        assert_eq!(crc8(&[0x01]), 0x4d);
+        assert_eq!(crc8(&FRAME[..PACKET_LEN - 1]), FRAME[PACKET_LEN - 1]);
+    }
+
+    #[test]
+    fn test_encode() {
+        let points = core::array::from_fn(|i| (500 + 10 * i as u16, 200));
+        assert_eq!(encode(3600, 10000, 10880, 12345, &points), FRAME);
+    }
+
+    #[test]
+    fn test_parse_packet() {
+        let mut parser = Ld06Parser::new();
+        let mut packets = parser.feed(&FRAME);
+        let packet = packets.next().unwrap().unwrap();
+        assert!(packets.next().is_none());
+
+        assert_eq!(packet.speed, 3600);
+        assert_eq!(packet.timestamp, 12345);
+        assert_eq!(packet.points.len(), POINT_PER_PACK);
+        for (i, point) in packet.points.iter().enumerate() {
+            assert!((angle::to_degrees(point.angle) - (100.0 + 0.8 * i as f32)).abs() < 1e-3);
+            assert_eq!(point.distance, 500 + 10 * i as u16);
+            assert_eq!(point.intensity, 200);
+            assert_eq!(point.timestamp, 0);
+        }
+    }
+
+    #[test]
+    fn test_parse_wraparound() {
+        let mut parser = Ld06Parser::new();
+        let packet = parser.feed(&FRAME_WRAP).next().unwrap().unwrap();
+
+        let angles: std::vec::Vec<f32> = packet
+            .points
+            .iter()
+            .map(|p| angle::to_degrees(p.angle))
+            .collect();
+        assert!((angles[0] - 355.0).abs() < 1e-3);
+        assert!((angles[6] - 359.8).abs() < 1e-3);
+        assert!((angles[7] - 0.6).abs() < 1e-3);
+        assert!((angles[11] - 3.8).abs() < 1e-3);
+        assert!(angles.iter().all(|&a| (0.0..360.0).contains(&a)));
+    }
+
+    #[test]
+    fn test_resync_after_garbage_and_crc_error() {
+        let mut stream = std::vec::Vec::new();
+        // 噪声中混入假帧头
+        stream.extend_from_slice(&[0x00, 0x54, 0x11, 0x54, 0x54, 0xFF]);
+        // 损坏的数据包
+        let mut corrupted = FRAME;
+        corrupted[10] ^= 0xFF;
+        stream.extend_from_slice(&corrupted);
+        stream.extend_from_slice(&FRAME);
+        stream.extend_from_slice(&FRAME_WRAP);
+
+        let mut parser = Ld06Parser::new();
+        let results: std::vec::Vec<_> = parser.feed(&stream).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], Err(ParseError::Crc { .. })));
+        assert_eq!(results[1].as_ref().unwrap().timestamp, 12345);
+        assert_eq!(results[2].as_ref().unwrap().timestamp, 29990);
+    }
+
+    #[test]
+    fn test_model_range_and_span() {
+        // 500mm起的距离在STL-26的量程内,但起始角度跨度按STL-27L的测量频率明显过大
+        let mut parser = Ld06Parser::with_model(LidarModel::Stl26);
+        let packet = parser.feed(&FRAME).next().unwrap().unwrap();
+        assert!(packet.points.iter().all(|p| p.distance >= 500));
+
+        let mut parser = Ld06Parser::with_model(LidarModel::Stl27l);
+        let result = parser.feed(&FRAME).next().unwrap();
+        assert_eq!(result.unwrap_err(), ParseError::AngleSpan);
+    }
+
+    #[test]
+    fn test_byte_by_byte_split() {
+        let mut parser = Ld06Parser::new();
+        let (head, tail) = FRAME.split_at(20);
+        assert!(parser.feed(head).next().is_none());
+        assert!(parser.feed(tail).next().unwrap().is_ok());
+    }
+}
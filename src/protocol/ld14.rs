@@ -0,0 +1,323 @@
+//! LD14/LD14P 数据协议
+//!
+//! 数据帧长度可变,多字节字段为大端字节序:
+//!
+//! | 偏移 | 长度 | 内容 |
+//! | ---- | ---- | ---- |
+//! | 0 | 1 | 帧头 0xAA |
+//! | 1 | 2 | 帧长度(帧头到负载末尾) |
+//! | 3 | 1 | 协议版本 0x01 |
+//! | 4 | 1 | 帧类型 0x61 |
+//! | 5 | 1 | 命令字 |
+//! | 6 | 2 | 负载长度 |
+//! | 8 | N | 负载 |
+//! | 8+N | 2 | 校验和(帧头到负载末尾所有字节之和) |
+//!
+//! 测量数据帧(命令字0xAD)的负载:
+//!
+//! | 偏移 | 长度 | 内容 |
+//! | ---- | ---- | ---- |
+//! | 0 | 1 | 转速(0.05转/秒) |
+//! | 1 | 2 | 零位偏移(0.01度,有符号) |
+//! | 3 | 2 | 起始角度(0.01度) |
+//! | 5 | 3*M | M个测量点,每点强度(1字节)+距离(0.25mm,2字节) |
+//!
+//! 每圈16帧,每帧覆盖22.5度。设备状态帧(命令字0xAE)的负载只有1字节转速
+//!
+//! # 实验性
+//!
+//! 以上帧格式只根据协议文档实现,还没有用实测录制数据验证。使用官方SDK的LD14P
+//! 输出的是230400波特率、0x54帧头、CRC8校验的LD06协议。在验证之前,
+//! [`LidarModel`] 的按名称解析不接受LD14和LD14P,只能在代码中或通过 `model-ld14*` 特性选择
+
+use heapless::Vec;
+
+use super::{Packet, ParseError};
+use crate::angle;
+use crate::filter::slbf::PointData;
+use crate::model::LidarModel;
+
+/// 帧头
+pub const PKG_HEADER: u8 = 0xAA;
+/// 协议版本
+pub const PROTOCOL_VERSION: u8 = 0x01;
+/// 帧类型
+pub const FRAME_TYPE: u8 = 0x61;
+/// 命令字: 测量数据
+pub const CMD_MEASUREMENT: u8 = 0xAD;
+/// 命令字: 设备状态
+pub const CMD_HEALTH: u8 = 0xAE;
+/// 单帧最大测量点数
+pub const MAX_POINT_PER_PACK: usize = 48;
+/// 每圈帧数
+pub const FRAMES_PER_REVOLUTION: u16 = 16;
+
+/// 帧头到负载起始的长度
+const HEADER_LEN: usize = 8;
+/// 测量数据负载中测量点之前的长度
+const MEASUREMENT_HEADER_LEN: usize = 5;
+/// 最大帧长度(含校验和)
+const MAX_FRAME_LEN: usize = HEADER_LEN + MEASUREMENT_HEADER_LEN + 3 * MAX_POINT_PER_PACK + 2;
+
+/// 计算校验和
+pub fn checksum(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0u16, |sum, &byte| sum.wrapping_add(byte as u16))
+}
+
+/// 携带 `points` 个测量点的测量数据帧长度(含校验和)
+pub const fn frame_len(points: usize) -> usize {
+    HEADER_LEN + MEASUREMENT_HEADER_LEN + 3 * points + 2
+}
+
+/// 读取大端u16
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// LD14/LD14P 流式解析器
+///
+/// 逐字节输入串口数据,在帧头处同步,校验通过后输出完整的数据包。
+/// 设备状态帧输出为只有转速、没有测量点的数据包,其他命令字的帧被忽略
+pub struct Ld14Parser {
+    /// 雷达型号
+    model: LidarModel,
+    /// 当前帧缓冲区
+    buf: [u8; MAX_FRAME_LEN],
+    /// 缓冲区中已接收的字节数
+    len: usize,
+}
+
+impl Default for Ld14Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ld14Parser {
+    /// 创建新的解析器实例
+    pub const fn new() -> Self {
+        Self::with_model(LidarModel::Ld14)
+    }
+
+    /// 创建指定型号的解析器实例
+    pub const fn with_model(model: LidarModel) -> Self {
+        Self {
+            model,
+            buf: [0; MAX_FRAME_LEN],
+            len: 0,
+        }
+    }
+
+    /// 丢弃已接收的不完整数据,重新等待帧头
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// 输入一个字节
+    ///
+    /// # Returns
+    /// * `None` - 数据帧尚未接收完整,或是被忽略的帧
+    /// * `Some(Ok(packet))` - 解析出一个完整的数据包
+    /// * `Some(Err(err))` - 数据帧接收完整但校验失败或内容无效
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, ParseError>> {
+        if self.len == 0 && byte != PKG_HEADER {
+            return None;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+
+        if !self.prefix_valid(0) {
+            self.resync();
+            return None;
+        }
+        if self.len < HEADER_LEN || self.len < self.frame_len() + 2 {
+            return None;
+        }
+
+        let frame_len = self.frame_len();
+        let expected = checksum(&self.buf[..frame_len]);
+        let actual = read_u16(&self.buf, frame_len);
+        if expected != actual {
+            self.resync();
+            return Some(Err(ParseError::Checksum { expected, actual }));
+        }
+
+        self.len = 0;
+        self.decode(frame_len)
+    }
+
+    /// 连续输入多个字节,依次返回解析结果
+    pub fn feed<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<Packet, ParseError>> + 'a {
+        bytes.iter().filter_map(move |&byte| self.push(byte))
+    }
+
+    /// 帧长度字段
+    fn frame_len(&self) -> usize {
+        read_u16(&self.buf, 1) as usize
+    }
+
+    /// 检查从 `start` 开始已接收的字节是否可能是合法的帧头
+    fn prefix_valid(&self, start: usize) -> bool {
+        let buf = &self.buf[start..self.len];
+        if buf.len() >= 3 {
+            let frame_len = read_u16(buf, 1) as usize;
+            if !(HEADER_LEN..=MAX_FRAME_LEN - 2).contains(&frame_len) {
+                return false;
+            }
+        }
+        (buf.len() < 4 || buf[3] == PROTOCOL_VERSION) && (buf.len() < 5 || buf[4] == FRAME_TYPE)
+    }
+
+    /// 在已接收的数据中查找下一个可能的帧头
+    fn resync(&mut self) {
+        let start = (1..self.len)
+            .find(|&i| self.buf[i] == PKG_HEADER && self.prefix_valid(i))
+            .unwrap_or(self.len);
+        self.buf.copy_within(start..self.len, 0);
+        self.len -= start;
+    }
+
+    /// 解码一个已通过校验的数据帧
+    fn decode(&self, frame_len: usize) -> Option<Result<Packet, ParseError>> {
+        let payload_len = read_u16(&self.buf, 6) as usize;
+        if HEADER_LEN + payload_len != frame_len {
+            return Some(Err(ParseError::InvalidLength));
+        }
+        let payload = &self.buf[HEADER_LEN..frame_len];
+
+        match self.buf[5] {
+            CMD_MEASUREMENT => Some(self.decode_measurement(payload)),
+            CMD_HEALTH => {
+                let speed = *payload.first()?;
+                Some(Ok(Packet {
+                    speed: speed as u16 * 18,
+                    start_angle: angle::from_centidegrees(0),
+                    end_angle: angle::from_centidegrees(0),
+                    timestamp: 0,
+                    points: Vec::new(),
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// 解码测量数据负载
+    fn decode_measurement(&self, payload: &[u8]) -> Result<Packet, ParseError> {
+        let count = payload.len().saturating_sub(MEASUREMENT_HEADER_LEN) / 3;
+        if payload.len() != MEASUREMENT_HEADER_LEN + count * 3 {
+            return Err(ParseError::InvalidLength);
+        }
+
+        // 0.05转/秒 = 18度/秒
+        let speed = payload[0] as u16 * 18;
+        let zero_offset = read_u16(payload, 1) as i16;
+        let start = (read_u16(payload, 3) as i32 + zero_offset as i32).rem_euclid(36000);
+        let start_angle = angle::from_centidegrees(start as u16);
+        let span = angle::from_centidegrees(36000 / FRAMES_PER_REVOLUTION);
+        let range = self.model.min_range()..=self.model.max_range();
+
+        let mut points = Vec::new();
+        for i in 0..count {
+            let offset = MEASUREMENT_HEADER_LEN + i * 3;
+            let angle = angle::interpolate(start_angle, span, i, count);
+            let mut distance = read_u16(payload, offset + 1) / 4;
+            if !range.contains(&distance) {
+                distance = 0;
+            }
+            points
+                .push(PointData {
+                    angle,
+                    distance,
+                    intensity: payload[offset],
+                    timestamp: 0,
+                })
+                .ok();
+        }
+
+        let end_angle =
+            angle::interpolate(start_angle, span, count.saturating_sub(1), count.max(1));
+
+        Ok(Packet {
+            speed,
+            start_angle,
+            end_angle,
+            timestamp: 0,
+            points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以下数据帧按协议文档手工构造,还没有LD14/LD14P的实测录制数据
+
+    /// 6转/秒,起始45.00度,8个点,距离800mm起每点递增5mm
+    const FRAME: [u8; 39] = [
+        0xAA, 0x00, 0x25, 0x01, 0x61, 0xAD, 0x00, 0x1D, 0x78, 0x00, 0x00, 0x11, 0x94, 0xB4, 0x0C,
+        0x80, 0xB4, 0x0C, 0x94, 0xB4, 0x0C, 0xA8, 0xB4, 0x0C, 0xBC, 0xB4, 0x0C, 0xD0, 0xB4, 0x0C,
+        0xE4, 0xB4, 0x0C, 0xF8, 0xB4, 0x0D, 0x0C, 0x0E, 0x49,
+    ];
+
+    /// 起始350.00度,零位偏移-0.50度,跨越0度
+    const FRAME_WRAP: [u8; 39] = [
+        0xAA, 0x00, 0x25, 0x01, 0x61, 0xAD, 0x00, 0x1D, 0x78, 0xFF, 0xCE, 0x88, 0xB8, 0xC8, 0x17,
+        0x70, 0xC8, 0x17, 0x70, 0xC8, 0x17, 0x70, 0xC8, 0x17, 0x70, 0xC8, 0x17, 0x70, 0xC8, 0x17,
+        0x70, 0xC8, 0x17, 0x70, 0xC8, 0x17, 0x70, 0x0F, 0xF8,
+    ];
+
+    /// 设备状态帧,6转/秒
+    const FRAME_HEALTH: [u8; 11] = [
+        0xAA, 0x00, 0x09, 0x01, 0x61, 0xAE, 0x00, 0x01, 0x78, 0x02, 0x3C,
+    ];
+
+    #[test]
+    fn test_parse_measurement() {
+        let mut parser = Ld14Parser::new();
+        let packet = parser.feed(&FRAME).next().unwrap().unwrap();
+
+        assert_eq!(packet.speed, 2160);
+        assert_eq!(packet.points.len(), 8);
+        for (i, point) in packet.points.iter().enumerate() {
+            // 每点2.8125度,定点表示下四舍五入到0.01度
+            assert!((angle::to_degrees(point.angle) - (45.0 + 2.8125 * i as f32)).abs() < 0.01);
+            assert_eq!(point.distance, 800 + 5 * i as u16);
+            assert_eq!(point.intensity, 180);
+        }
+    }
+
+    #[test]
+    fn test_parse_wraparound_with_offset() {
+        let mut parser = Ld14Parser::new();
+        let packet = parser.feed(&FRAME_WRAP).next().unwrap().unwrap();
+
+        assert!((angle::to_degrees(packet.points[0].angle) - 349.5).abs() < 1e-3);
+        assert!((angle::to_degrees(packet.points[4].angle) - 0.75).abs() < 1e-3);
+        assert!(packet.points.iter().all(|p| p.distance == 1500));
+    }
+
+    #[test]
+    fn test_health_frame_and_resync() {
+        let mut stream = std::vec::Vec::new();
+        stream.extend_from_slice(&[0xAA, 0xFF, 0xFF, 0x00, 0xAA, 0x00]);
+        let mut corrupted = FRAME;
+        corrupted[20] ^= 0x01;
+        stream.extend_from_slice(&corrupted);
+        stream.extend_from_slice(&FRAME_HEALTH);
+        stream.extend_from_slice(&FRAME);
+
+        let mut parser = Ld14Parser::new();
+        let results: std::vec::Vec<_> = parser.feed(&stream).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], Err(ParseError::Checksum { .. })));
+        let health = results[1].as_ref().unwrap();
+        assert_eq!(health.speed, 2160);
+        assert!(health.points.is_empty());
+        assert_eq!(results[2].as_ref().unwrap().points.len(), 8);
+    }
+}
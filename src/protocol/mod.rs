@@ -0,0 +1,107 @@
+pub mod ld06;
+pub mod ld14;
+
+use heapless::Vec;
+
+use crate::angle::Angle;
+use crate::filter::slbf::PointData;
+use crate::model::LidarModel;
+
+/// 单个数据包可携带的最大测量点数
+pub const MAX_POINTS_PER_PACKET: usize = if ld14::MAX_POINT_PER_PACK > ld06::POINT_PER_PACK {
+    ld14::MAX_POINT_PER_PACK
+} else {
+    ld06::POINT_PER_PACK
+};
+
+/// 数据协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Protocol {
+    /// 0x54帧头、固定47字节、CRC8校验
+    Ld06,
+    /// 0xAA帧头、可变长度、累加和校验
+    Ld14,
+}
+
+/// 解码后的雷达数据包
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet {
+    /// 转速(度/秒)
+    pub speed: u16,
+    /// 起始角度
+    pub start_angle: Angle,
+    /// 结束角度
+    pub end_angle: Angle,
+    /// 设备时间戳(毫秒),协议不提供时为0
+    pub timestamp: u16,
+    /// 测量点
+    pub points: Vec<PointData, MAX_POINTS_PER_PACKET>,
+}
+
+/// 协议解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// 校验失败
+    Crc {
+        /// 根据数据计算出的校验值
+        expected: u8,
+        /// 数据包中携带的校验值
+        actual: u8,
+    },
+    /// 累加和校验失败
+    Checksum {
+        /// 根据数据计算出的校验值
+        expected: u16,
+        /// 数据帧中携带的校验值
+        actual: u16,
+    },
+    /// 起止角度跨度与转速不符
+    AngleSpan,
+    /// 长度字段与数据不符
+    InvalidLength,
+}
+
+/// 按型号选择协议的解析器
+pub enum Parser {
+    /// LD06/LD19/STL系列
+    Ld06(ld06::Ld06Parser),
+    /// LD14/LD14P
+    Ld14(ld14::Ld14Parser),
+}
+
+impl Parser {
+    /// 创建指定型号的解析器
+    pub const fn for_model(model: LidarModel) -> Self {
+        match model.protocol() {
+            Protocol::Ld06 => Self::Ld06(ld06::Ld06Parser::with_model(model)),
+            Protocol::Ld14 => Self::Ld14(ld14::Ld14Parser::with_model(model)),
+        }
+    }
+
+    /// 输入一个字节,返回值与各协议解析器的 `push` 相同
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, ParseError>> {
+        match self {
+            Self::Ld06(parser) => parser.push(byte),
+            Self::Ld14(parser) => parser.push(byte),
+        }
+    }
+
+    /// 丢弃已接收的不完整数据
+    pub fn reset(&mut self) {
+        match self {
+            Self::Ld06(parser) => parser.reset(),
+            Self::Ld14(parser) => parser.reset(),
+        }
+    }
+
+    /// 连续输入多个字节,依次返回解析结果
+    pub fn feed<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<Packet, ParseError>> + 'a {
+        bytes.iter().filter_map(move |&byte| self.push(byte))
+    }
+}
@@ -0,0 +1,190 @@
+use crate::model::LidarModel;
+use crate::protocol::{Packet, ParseError, Parser};
+
+/// 单次从数据源读取的最大字节数
+const READ_BUF_LEN: usize = 64;
+
+/// 读取错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadError<E> {
+    /// 数据源错误
+    Io(E),
+    /// 协议解析错误
+    Parse(ParseError),
+    /// 数据源已结束
+    Eof,
+}
+
+/// 雷达数据读取器
+///
+/// 从任意字节数据源读取数据并解析为数据包,
+/// 数据源可以是MCU上的串口,也可以是主机上的文件或伪终端
+pub struct LidarReader<R> {
+    /// 数据源
+    io: R,
+    /// 协议解析器
+    parser: Parser,
+    /// 读取缓冲区
+    buf: [u8; READ_BUF_LEN],
+    /// 缓冲区中下一个待解析字节的位置
+    pos: usize,
+    /// 缓冲区中的有效字节数
+    len: usize,
+}
+
+impl<R> LidarReader<R> {
+    /// 创建新的读取器实例,使用默认型号
+    pub fn new(io: R) -> Self {
+        Self::with_model(io, LidarModel::DEFAULT)
+    }
+
+    /// 创建指定型号的读取器实例
+    pub fn with_model(io: R, model: LidarModel) -> Self {
+        Self {
+            io,
+            parser: Parser::for_model(model),
+            buf: [0; READ_BUF_LEN],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    /// 获取数据源的可变引用
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.io
+    }
+
+    /// 取回数据源,未解析的缓冲数据将被丢弃
+    pub fn into_inner(self) -> R {
+        self.io
+    }
+
+    /// 解析缓冲区中剩余的数据
+    fn parse_buffered(&mut self) -> Option<Result<Packet, ParseError>> {
+        while self.pos < self.len {
+            let byte = self.buf[self.pos];
+            self.pos += 1;
+            if let Some(result) = self.parser.push(byte) {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// 记录一次读取的结果
+    fn fill<E>(&mut self, result: Result<usize, E>) -> Result<(), ReadError<E>> {
+        let len = result.map_err(ReadError::Io)?;
+        if len == 0 {
+            return Err(ReadError::Eof);
+        }
+        self.pos = 0;
+        self.len = len;
+        Ok(())
+    }
+}
+
+impl<R: embedded_io_async::Read> LidarReader<R> {
+    /// 读取下一个数据包
+    pub async fn read_packet(&mut self) -> Result<Packet, ReadError<R::Error>> {
+        loop {
+            if let Some(result) = self.parse_buffered() {
+                return result.map_err(ReadError::Parse);
+            }
+            let result = self.io.read(&mut self.buf).await;
+            self.fill(result)?;
+        }
+    }
+}
+
+impl<R: embedded_io::Read> LidarReader<R> {
+    /// 以阻塞方式读取下一个数据包
+    pub fn read_packet_blocking(&mut self) -> Result<Packet, ReadError<R::Error>> {
+        loop {
+            if let Some(result) = self.parse_buffered() {
+                return result.map_err(ReadError::Parse);
+            }
+            let result = self.io.read(&mut self.buf);
+            self.fill(result)?;
+        }
+    }
+}
+
+/// 将 `std::io::Read` 适配为 `embedded_io` 数据源,用于在主机上读取录制文件或伪终端
+#[cfg(feature = "std")]
+pub struct StdReader<R>(pub R);
+
+#[cfg(feature = "std")]
+impl<R> embedded_io::ErrorType for StdReader<R> {
+    type Error = std::io::Error;
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> embedded_io::Read for StdReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            match self.0.read(buf) {
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> embedded_io_async::Read for StdReader<R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        embedded_io::Read::read(self, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 转速3600度/秒,时间戳12345的数据包
+    const FRAME: [u8; 47] = [
+        0x54, 0x2C, 0x10, 0x0E, 0x10, 0x27, 0xF4, 0x01, 0xC8, 0xFE, 0x01, 0xC8, 0x08, 0x02, 0xC8,
+        0x12, 0x02, 0xC8, 0x1C, 0x02, 0xC8, 0x26, 0x02, 0xC8, 0x30, 0x02, 0xC8, 0x3A, 0x02, 0xC8,
+        0x44, 0x02, 0xC8, 0x4E, 0x02, 0xC8, 0x58, 0x02, 0xC8, 0x62, 0x02, 0xC8, 0x80, 0x2A, 0x39,
+        0x30, 0x89,
+    ];
+
+    fn stream() -> std::vec::Vec<u8> {
+        let mut bytes = std::vec![0x00, 0x54];
+        for _ in 0..3 {
+            bytes.extend_from_slice(&FRAME);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_blocking() {
+        let bytes = stream();
+        let mut reader = LidarReader::new(&bytes[..]);
+        for _ in 0..3 {
+            let packet = reader.read_packet_blocking().unwrap();
+            assert_eq!(packet.timestamp, 12345);
+        }
+        assert_eq!(reader.read_packet_blocking().unwrap_err(), ReadError::Eof);
+    }
+
+    #[test]
+    fn test_read_async() {
+        let bytes = stream();
+        let mut reader = LidarReader::new(&bytes[..]);
+        embassy_futures::block_on(async {
+            for _ in 0..3 {
+                assert_eq!(reader.read_packet().await.unwrap().speed, 3600);
+            }
+            assert_eq!(reader.read_packet().await.unwrap_err(), ReadError::Eof);
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_reader() {
+        let mut reader = LidarReader::new(StdReader(std::io::Cursor::new(stream())));
+        assert!(reader.read_packet_blocking().is_ok());
+    }
+}
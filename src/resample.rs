@@ -0,0 +1,351 @@
+//! 角度重采样
+//!
+//! 雷达每圈输出的点角度都不相同,下游通常需要固定角度网格上的数据。
+//! [`Resampler`] 将一圈的点按固定角度间隔划分为 `BINS` 个区间,输出与ROS LaserScan
+//! 相同格式的距离和强度数组。第 `i` 个区间的中心角度为 `i * 360 / BINS` 度
+
+use crate::angle::{self, Angle};
+use crate::filter::slbf::PointData;
+use crate::scan::Scan;
+
+/// 区间内有多个点时的取值方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResamplePolicy {
+    /// 取角度最接近区间中心的点
+    Nearest,
+    /// 取距离最近的点,适合避障
+    MinRange,
+    /// 取强度最大的点
+    MaxIntensity,
+    /// 用区间中心两侧的点线性插值,点云应按角度排序
+    Interpolate,
+}
+
+/// 空区间的距离值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EmptyBin {
+    /// 正无穷,与ROS REP 117中"没有回波"的约定相同
+    Infinity,
+    /// NaN,表示测量无效
+    NaN,
+}
+
+impl EmptyBin {
+    /// 对应的距离值
+    fn value(self) -> f32 {
+        match self {
+            Self::Infinity => f32::INFINITY,
+            Self::NaN => f32::NAN,
+        }
+    }
+}
+
+/// 重采样配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResampleConfig {
+    /// 取值方式
+    pub policy: ResamplePolicy,
+    /// 空区间的距离值
+    pub empty: EmptyBin,
+    /// 插值时相邻两点间允许的最大角度差,超过时两点之间的区间为空
+    pub max_gap: Angle,
+}
+
+impl Default for ResampleConfig {
+    fn default() -> Self {
+        Self {
+            policy: ResamplePolicy::Nearest,
+            empty: EmptyBin::Infinity,
+            max_gap: angle::from_degrees(2.0),
+        }
+    }
+}
+
+/// 重采样配置错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResampleConfigError {
+    /// 区间数为0
+    Bins,
+    /// 最大角度差无效
+    MaxGap,
+}
+
+impl ResampleConfig {
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), ResampleConfigError> {
+        if !angle::is_valid(self.max_gap) {
+            return Err(ResampleConfigError::MaxGap);
+        }
+        Ok(())
+    }
+}
+
+/// 固定角度网格上的一圈扫描数据
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LaserScan<const BINS: usize> {
+    /// 各区间的距离(m),空区间为 [`EmptyBin`] 指定的值
+    pub ranges: [f32; BINS],
+    /// 各区间的强度,空区间为0
+    pub intensities: [f32; BINS],
+    /// 第一个点的时间戳
+    pub start_timestamp: u64,
+    /// 最后一个点的时间戳
+    pub end_timestamp: u64,
+}
+
+impl<const BINS: usize> Default for LaserScan<BINS> {
+    fn default() -> Self {
+        Self {
+            ranges: [f32::INFINITY; BINS],
+            intensities: [0.0; BINS],
+            start_timestamp: 0,
+            end_timestamp: 0,
+        }
+    }
+}
+
+impl<const BINS: usize> LaserScan<BINS> {
+    /// 相邻区间的角度间隔(弧度)
+    pub fn angle_increment(&self) -> f32 {
+        2.0 * core::f32::consts::PI / BINS as f32
+    }
+
+    /// 第 `index` 个区间的中心角度(弧度)
+    pub fn angle(&self, index: usize) -> f32 {
+        self.angle_increment() * index as f32
+    }
+}
+
+/// 角度重采样器
+pub struct Resampler<const BINS: usize> {
+    /// 配置参数
+    config: ResampleConfig,
+}
+
+impl<const BINS: usize> Resampler<BINS> {
+    /// 创建新的重采样器
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ResampleConfigError`]
+    pub fn new(config: ResampleConfig) -> Result<Self, ResampleConfigError> {
+        if BINS == 0 {
+            return Err(ResampleConfigError::Bins);
+        }
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    /// 重采样一圈扫描数据,同时复制时间戳
+    pub fn resample_scan<const N: usize>(&self, scan: &Scan<N>, out: &mut LaserScan<BINS>) {
+        self.resample(&scan.points, out);
+        out.start_timestamp = scan.start_timestamp;
+        out.end_timestamp = scan.end_timestamp;
+    }
+
+    /// 重采样点云,覆盖 `out` 中的距离和强度
+    ///
+    /// 距离为0或角度无效的点被忽略
+    pub fn resample(&self, points: &[PointData], out: &mut LaserScan<BINS>) {
+        // 先以正无穷标记空区间,便于比较距离,最后再替换为配置的值
+        out.ranges = [f32::INFINITY; BINS];
+        out.intensities = [0.0; BINS];
+
+        let valid = points
+            .iter()
+            .filter(|p| p.distance != 0 && angle::is_valid(p.angle));
+        match self.config.policy {
+            ResamplePolicy::Nearest => {
+                let mut best = [f32::INFINITY; BINS];
+                for point in valid {
+                    let (bin, offset) = nearest_bin::<BINS>(point.angle);
+                    if offset < best[bin] {
+                        best[bin] = offset;
+                        set(out, bin, point);
+                    }
+                }
+            }
+            ResamplePolicy::MinRange => {
+                for point in valid {
+                    let (bin, _) = nearest_bin::<BINS>(point.angle);
+                    if range(point) < out.ranges[bin] {
+                        set(out, bin, point);
+                    }
+                }
+            }
+            ResamplePolicy::MaxIntensity => {
+                for point in valid {
+                    let (bin, _) = nearest_bin::<BINS>(point.angle);
+                    let intensity = point.intensity as f32;
+                    if out.ranges[bin] == f32::INFINITY || intensity > out.intensities[bin] {
+                        set(out, bin, point);
+                    }
+                }
+            }
+            ResamplePolicy::Interpolate => self.interpolate(points, out),
+        }
+
+        let empty = self.config.empty.value();
+        for range in out.ranges.iter_mut() {
+            if *range == f32::INFINITY {
+                *range = empty;
+            }
+        }
+    }
+
+    /// 在每对相邻点之间线性插值,首尾两点也视为相邻
+    fn interpolate(&self, points: &[PointData], out: &mut LaserScan<BINS>) {
+        let mut valid = points
+            .iter()
+            .filter(|p| p.distance != 0 && angle::is_valid(p.angle));
+        let Some(first) = valid.next() else {
+            return;
+        };
+        let max_gap = angle::to_degrees(self.config.max_gap);
+        let mut prev = first;
+        for point in valid.chain(core::iter::once(first)) {
+            let mut gap = angle::to_degrees(point.angle) - angle::to_degrees(prev.angle);
+            if gap < 0.0 {
+                gap += 360.0;
+            }
+            if gap > 0.0 && gap <= max_gap {
+                self.fill_between(prev, point, gap, out);
+            }
+            prev = point;
+        }
+    }
+
+    /// 填充中心角度位于 `[a, b)` 内的区间
+    fn fill_between(&self, a: &PointData, b: &PointData, gap: f32, out: &mut LaserScan<BINS>) {
+        let scale = BINS as f32 / 360.0;
+        let start = angle::to_degrees(a.angle) * scale;
+        let end = start + gap * scale;
+        // 向上取整
+        let mut bin = start as usize;
+        if (bin as f32) < start {
+            bin += 1;
+        }
+        while (bin as f32) < end {
+            let t = (bin as f32 - start) / (end - start);
+            out.ranges[bin % BINS] = range(a) + (range(b) - range(a)) * t;
+            out.intensities[bin % BINS] =
+                a.intensity as f32 + (b.intensity as f32 - a.intensity as f32) * t;
+            bin += 1;
+        }
+    }
+}
+
+/// 距离(m)
+fn range(point: &PointData) -> f32 {
+    point.distance as f32 / 1000.0
+}
+
+/// 写入一个区间
+fn set<const BINS: usize>(out: &mut LaserScan<BINS>, bin: usize, point: &PointData) {
+    out.ranges[bin] = range(point);
+    out.intensities[bin] = point.intensity as f32;
+}
+
+/// 中心角度最接近 `angle` 的区间,以及与区间中心的距离(以区间间隔为单位)
+fn nearest_bin<const BINS: usize>(angle: Angle) -> (usize, f32) {
+    let position = angle::to_degrees(angle) * BINS as f32 / 360.0;
+    let bin = (position + 0.5) as usize;
+    // `f32::abs` 在core中不可用
+    let offset = position - bin as f32;
+    ((bin % BINS), if offset < 0.0 { -offset } else { offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(centidegrees: u16, distance: u16, intensity: u8) -> PointData {
+        PointData {
+            angle: angle::from_centidegrees(centidegrees),
+            distance,
+            intensity,
+            timestamp: 0,
+        }
+    }
+
+    fn resample<const BINS: usize>(
+        policy: ResamplePolicy,
+        empty: EmptyBin,
+        points: &[PointData],
+    ) -> LaserScan<BINS> {
+        let resampler = Resampler::<BINS>::new(ResampleConfig {
+            policy,
+            empty,
+            ..ResampleConfig::default()
+        })
+        .unwrap();
+        let mut out = LaserScan::default();
+        resampler.resample(points, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_bin_selection_policies() {
+        // 同一个1度区间内的三个点
+        let points = [
+            point(980, 1500, 100),
+            point(1010, 2000, 50),
+            point(1040, 1200, 200),
+        ];
+
+        let out = resample::<360>(ResamplePolicy::Nearest, EmptyBin::Infinity, &points);
+        assert_eq!(out.ranges[10], 2.0);
+        assert_eq!(out.intensities[10], 50.0);
+        assert_eq!(out.ranges[11], f32::INFINITY);
+        assert_eq!(out.intensities[11], 0.0);
+
+        let out = resample::<360>(ResamplePolicy::MinRange, EmptyBin::Infinity, &points);
+        assert_eq!(out.ranges[10], 1.2);
+
+        let out = resample::<360>(ResamplePolicy::MaxIntensity, EmptyBin::NaN, &points);
+        assert_eq!(out.ranges[10], 1.2);
+        assert_eq!(out.intensities[10], 200.0);
+        assert!(out.ranges[9].is_nan());
+    }
+
+    #[test]
+    fn test_nearest_wraps_to_first_bin() {
+        let points = [point(35980, 1000, 100), point(100, 3000, 100)];
+        let out = resample::<360>(ResamplePolicy::Nearest, EmptyBin::Infinity, &points);
+        assert_eq!(out.ranges[0], 1.0);
+        assert_eq!(out.ranges[1], 3.0);
+        assert_eq!(out.ranges[359], f32::INFINITY);
+    }
+
+    #[test]
+    fn test_interpolate_across_zero_and_gaps() {
+        // 359.5度到0.5度之间跨越0度,1.5度到5.5度之间的角度差超过2度
+        let points = [
+            point(50, 2000, 100),
+            point(150, 3000, 200),
+            point(550, 1000, 100),
+            point(35950, 1000, 0),
+        ];
+        let out = resample::<720>(ResamplePolicy::Interpolate, EmptyBin::NaN, &points);
+        let expected = [(719, 1.0), (0, 1.5), (1, 2.0), (2, 2.5)];
+        for (bin, range) in expected {
+            assert!((out.ranges[bin] - range).abs() < 1e-5, "bin {bin}");
+        }
+        assert!((out.intensities[2] - 150.0).abs() < 1e-3);
+        assert!(out.ranges[3].is_nan());
+        assert!(out.ranges[10].is_nan());
+        assert!((out.angle(180) - core::f32::consts::FRAC_PI_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert_eq!(
+            Resampler::<0>::new(ResampleConfig::default()).err(),
+            Some(ResampleConfigError::Bins)
+        );
+    }
+}
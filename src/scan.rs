@@ -0,0 +1,313 @@
+use heapless::Vec;
+
+use crate::angle::{self, Angle, FULL_TURN, HALF_TURN};
+use crate::filter::slbf::PointData;
+use crate::model::LidarModel;
+
+/// 相邻两点间允许的默认最大角度差(5度)
+#[cfg(not(feature = "fixed-point"))]
+pub const DEFAULT_MAX_GAP: Angle = 5.0;
+/// 相邻两点间允许的默认最大角度差(5度)
+#[cfg(feature = "fixed-point")]
+pub const DEFAULT_MAX_GAP: Angle = 500;
+
+/// 一圈完整的扫描数据
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Scan<const N: usize> {
+    /// 按采样顺序排列的测量点
+    pub points: Vec<PointData, N>,
+    /// 第一个点的时间戳
+    pub start_timestamp: u64,
+    /// 最后一个点的时间戳
+    pub end_timestamp: u64,
+    /// 本圈平均转速(度/秒)
+    pub speed: u16,
+}
+
+impl<const N: usize> Default for Scan<N> {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            start_timestamp: 0,
+            end_timestamp: 0,
+            speed: 0,
+        }
+    }
+}
+
+impl<const N: usize> Scan<N> {
+    /// 点数
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// 是否没有任何点
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// 扫描不完整的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IncompleteReason {
+    /// 从一圈的中途开始接收,例如启动后或重新同步后的第一圈
+    PartialStart,
+    /// 相邻点角度不连续,通常是丢失了数据包
+    AngleGap,
+    /// 点数超过缓冲区容量
+    Overflow,
+}
+
+/// 被丢弃的不完整扫描
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IncompleteScan {
+    /// 不完整的原因
+    pub reason: IncompleteReason,
+    /// 被丢弃的点数
+    pub point_count: usize,
+    /// 第一个点的时间戳
+    pub start_timestamp: u64,
+    /// 最后一个点的时间戳
+    pub end_timestamp: u64,
+}
+
+/// 扫描拼接事件
+#[derive(Debug)]
+pub enum ScanEvent<'a, const N: usize> {
+    /// 完成一圈扫描,可在下一次输入前就地处理
+    Complete(&'a mut Scan<N>),
+    /// 当前一圈不完整,已被丢弃
+    Incomplete(IncompleteScan),
+}
+
+/// 扫描拼接器
+///
+/// 按采样顺序接收测量点,在角度从360度回到0度时输出一整圈数据。
+/// 跨越0度的数据包会被拆分到前后两圈中
+pub struct ScanAssembler<const N: usize> {
+    /// 正在拼接的一圈
+    scan: Scan<N>,
+    /// 当前一圈是否从0度开始
+    aligned: bool,
+    /// 上一个点的角度
+    last_angle: Option<Angle>,
+    /// 触发输出的点,留到下一次输入时加入新的一圈
+    carry: Option<(PointData, u16)>,
+    /// 转速累加值
+    speed_sum: u32,
+    /// 相邻点允许的最大角度差
+    max_gap: Angle,
+}
+
+impl<const N: usize> Default for ScanAssembler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ScanAssembler<N> {
+    /// 创建新的拼接器实例
+    pub fn new() -> Self {
+        Self {
+            scan: Scan::default(),
+            aligned: false,
+            last_angle: None,
+            carry: None,
+            speed_sum: 0,
+            max_gap: DEFAULT_MAX_GAP,
+        }
+    }
+
+    /// 创建按指定型号设置角度间隔阈值的拼接器实例
+    pub fn with_model(model: LidarModel) -> Self {
+        let mut assembler = Self::new();
+        assembler.set_max_gap(model.max_gap());
+        assembler
+    }
+
+    /// 设置相邻点允许的最大角度差,超过时认为丢失了数据
+    pub fn set_max_gap(&mut self, max_gap: Angle) {
+        self.max_gap = max_gap;
+    }
+
+    /// 丢弃已接收的数据,下一圈将被视为不完整
+    pub fn reset(&mut self) {
+        self.restart(false);
+        self.last_angle = None;
+        self.carry = None;
+    }
+
+    /// 输入一个测量点
+    ///
+    /// # Arguments
+    /// * `point` - 测量点
+    /// * `speed` - 该点所在数据包的转速(度/秒)
+    ///
+    /// # Returns
+    /// * `Some(ScanEvent::Complete)` - 完成一圈
+    /// * `Some(ScanEvent::Incomplete)` - 丢弃了不完整的一圈
+    pub fn push(&mut self, point: PointData, speed: u16) -> Option<ScanEvent<'_, N>> {
+        if let Some((carried, carried_speed)) = self.carry.take() {
+            self.restart(true);
+            self.append(carried, carried_speed);
+        }
+
+        let Some(last_angle) = self.last_angle else {
+            self.append(point, speed);
+            return None;
+        };
+
+        if point.angle < last_angle && last_angle - point.angle > HALF_TURN {
+            // 角度从360度回到0度
+            if FULL_TURN - last_angle + point.angle > self.max_gap {
+                return Some(self.discard(IncompleteReason::AngleGap, false, point, speed));
+            }
+            if !self.aligned {
+                return Some(self.discard(IncompleteReason::PartialStart, true, point, speed));
+            }
+            self.scan.speed = (self.speed_sum / self.scan.len() as u32) as u16;
+            self.last_angle = Some(point.angle);
+            self.carry = Some((point, speed));
+            return Some(ScanEvent::Complete(&mut self.scan));
+        }
+
+        if angle::abs_diff(point.angle, last_angle) > self.max_gap {
+            return Some(self.discard(IncompleteReason::AngleGap, false, point, speed));
+        }
+        if self.scan.points.is_full() {
+            return Some(self.discard(IncompleteReason::Overflow, false, point, speed));
+        }
+
+        self.append(point, speed);
+        None
+    }
+
+    /// 丢弃当前一圈,并以新的点开始下一圈
+    fn discard(
+        &mut self,
+        reason: IncompleteReason,
+        aligned: bool,
+        point: PointData,
+        speed: u16,
+    ) -> ScanEvent<'_, N> {
+        let incomplete = IncompleteScan {
+            reason,
+            point_count: self.scan.len(),
+            start_timestamp: self.scan.start_timestamp,
+            end_timestamp: self.scan.end_timestamp,
+        };
+        self.restart(aligned);
+        self.append(point, speed);
+        ScanEvent::Incomplete(incomplete)
+    }
+
+    /// 清空当前一圈
+    fn restart(&mut self, aligned: bool) {
+        self.scan.points.clear();
+        self.scan.speed = 0;
+        self.speed_sum = 0;
+        self.aligned = aligned;
+    }
+
+    /// 向当前一圈追加一个点
+    fn append(&mut self, point: PointData, speed: u16) {
+        if self.scan.is_empty() {
+            self.scan.start_timestamp = point.timestamp;
+        }
+        self.scan.end_timestamp = point.timestamp;
+        self.last_angle = Some(point.angle);
+        self.speed_sum += speed as u32;
+        self.scan.points.push(point).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按0.8度间隔生成从 `start` 开始的 `count` 个点,时间戳为点的序号
+    fn points(start: f32, count: usize) -> impl Iterator<Item = PointData> {
+        (0..count).map(move |i| {
+            let mut angle = start + 0.8 * i as f32;
+            while angle >= 360.0 {
+                angle -= 360.0;
+            }
+            PointData {
+                angle: angle::from_degrees(angle),
+                distance: 1000,
+                intensity: 200,
+                timestamp: i as u64,
+            }
+        })
+    }
+
+    #[test]
+    fn test_assemble_revolutions() {
+        let mut assembler = ScanAssembler::<512>::new();
+        let mut events = std::vec::Vec::new();
+        // 从100.4度开始转2.5圈
+        for point in points(100.4, 1125) {
+            match assembler.push(point, 3600) {
+                Some(ScanEvent::Complete(scan)) => events.push(Ok((
+                    scan.len(),
+                    scan.start_timestamp,
+                    scan.end_timestamp,
+                    scan.speed,
+                    scan.points[0].angle,
+                ))),
+                Some(ScanEvent::Incomplete(incomplete)) => events.push(Err(incomplete)),
+                None => {}
+            }
+        }
+
+        assert_eq!(events.len(), 2);
+        let incomplete = events[0].unwrap_err();
+        assert_eq!(incomplete.reason, IncompleteReason::PartialStart);
+        assert_eq!(incomplete.point_count, 325);
+
+        let (len, start, end, speed, first_angle) = events[1].unwrap();
+        assert_eq!(len, 450);
+        assert_eq!((start, end), (325, 774));
+        assert_eq!(speed, 3600);
+        assert!(angle::to_degrees(first_angle) < 0.8);
+    }
+
+    #[test]
+    fn test_angle_gap_is_reported() {
+        let mut assembler = ScanAssembler::<512>::new();
+        let mut reasons = std::vec::Vec::new();
+        // 第二圈中途丢失一个数据包(12个点)
+        for (i, point) in points(0.0, 1350).enumerate() {
+            if (700..712).contains(&i) {
+                continue;
+            }
+            if let Some(ScanEvent::Incomplete(incomplete)) = assembler.push(point, 3600) {
+                reasons.push(incomplete.reason);
+            }
+        }
+
+        assert_eq!(
+            reasons,
+            [
+                IncompleteReason::PartialStart,
+                IncompleteReason::AngleGap,
+                IncompleteReason::PartialStart,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let mut assembler = ScanAssembler::<100>::new();
+        let mut reasons = std::vec::Vec::new();
+        for point in points(0.0, 450) {
+            if let Some(ScanEvent::Incomplete(incomplete)) = assembler.push(point, 3600) {
+                reasons.push(incomplete.reason);
+            }
+        }
+        assert!(reasons.contains(&IncompleteReason::Overflow));
+    }
+}
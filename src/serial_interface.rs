@@ -1,22 +1,167 @@
-pub struct SerialInterface {
+use embassy_stm32::usart::{self, BasicInstance, RingBufferedUartRx, Uart, UartTx};
 
+use crate::model::LidarModel;
+
+/// 串口错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// 帧错误
+    Framing,
+    /// 接收溢出
+    Overrun,
+    /// 噪声错误
+    Noise,
+    /// 奇偶校验错误
+    Parity,
+    /// 串口未打开
+    NotOpen,
+    /// 串口配置无效
+    Config,
+}
+
+impl From<usart::Error> for Error {
+    fn from(err: usart::Error) -> Self {
+        match err {
+            usart::Error::Framing => Error::Framing,
+            usart::Error::Noise => Error::Noise,
+            usart::Error::Overrun => Error::Overrun,
+            usart::Error::Parity => Error::Parity,
+            // DMA缓冲区相关错误均表现为数据丢失
+            _ => Error::Overrun,
+        }
+    }
+}
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Error::Framing | Error::Noise | Error::Parity => embedded_io::ErrorKind::InvalidData,
+            Error::Overrun => embedded_io::ErrorKind::Other,
+            Error::NotOpen => embedded_io::ErrorKind::NotConnected,
+            Error::Config => embedded_io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
+/// 雷达串口配置: 8N1,波特率由型号决定
+pub fn config(model: LidarModel) -> usart::Config {
+    let mut config = usart::Config::default();
+    config.baudrate = model.baud_rate();
+    config
+}
+
+/// 雷达串口
+///
+/// 发送使用DMA,接收使用DMA环形缓冲区,避免高波特率下丢失数据
+pub struct SerialInterface<'d, T: BasicInstance, TxDma, RxDma: usart::RxDma<T>> {
+    /// 发送端
+    tx: UartTx<'d, T, TxDma>,
+    /// 环形缓冲接收端
+    rx: RingBufferedUartRx<'d, T, RxDma>,
+    /// 串口配置,关闭时用于停止接收
+    config: usart::Config,
+    /// 是否已打开
+    is_open: bool,
 }
 
-impl SerialInterface {
+impl<'d, T, TxDma, RxDma> SerialInterface<'d, T, TxDma, RxDma>
+where
+    T: BasicInstance,
+    TxDma: usart::TxDma<T>,
+    RxDma: usart::RxDma<T>,
+{
+    /// 创建串口实例
+    ///
+    /// # Arguments
+    /// * `uart` - 使用 [`config`] 配置的串口
+    /// * `rx_buf` - DMA环形缓冲区
+    /// * `config` - 创建 `uart` 时使用的配置
+    pub fn new(
+        uart: Uart<'d, T, TxDma, RxDma>,
+        rx_buf: &'d mut [u8],
+        config: usart::Config,
+    ) -> Self {
+        let (tx, rx) = uart.split();
+        Self {
+            tx,
+            rx: rx.into_ring_buffered(rx_buf),
+            config,
+            is_open: false,
+        }
+    }
 
-    pub fn open(self) -> Result<Self, Error> {
+    /// 打开串口,清空环形缓冲区并启动DMA接收
+    pub fn open(&mut self) -> Result<(), Error> {
+        self.rx.start()?;
+        self.is_open = true;
+        Ok(())
     }
 
-    pub fn close(self) -> Result<Self, Error> {
+    /// 关闭串口,停止DMA接收,之后的读写均返回 [`Error::NotOpen`]
+    pub fn close(&mut self) -> Result<(), Error> {
+        if !self.is_open {
+            return Err(Error::NotOpen);
+        }
+        self.is_open = false;
+        // 重新应用配置会先停止DMA和接收中断,环形缓冲区不再被覆盖
+        self.rx
+            .set_config(&self.config)
+            .map_err(|_| Error::Config)
     }
 
-    pub fn read_from_io(self) -> Result<Self, Error> {
+    /// 读取串口数据
+    ///
+    /// # Returns
+    /// * 读取到的字节数
+    pub async fn read_from_io(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if !self.is_open {
+            return Err(Error::NotOpen);
+        }
+        self.rx.read(buf).await.map_err(Error::from)
     }
 
-    pub fn write_to_io(self) -> Result<Self, Error> {
+    /// 发送数据
+    pub async fn write_to_io(&mut self, buf: &[u8]) -> Result<(), Error> {
+        if !self.is_open {
+            return Err(Error::NotOpen);
+        }
+        self.tx.write(buf).await.map_err(Error::from)
     }
 
-    pub fn is_open(self) -> bool {
+    /// 串口是否已打开
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
+
+impl<'d, T, TxDma, RxDma> embedded_io_async::ErrorType for SerialInterface<'d, T, TxDma, RxDma>
+where
+    T: BasicInstance,
+    RxDma: usart::RxDma<T>,
+{
+    type Error = Error;
+}
+
+impl<'d, T, TxDma, RxDma> embedded_io_async::Read for SerialInterface<'d, T, TxDma, RxDma>
+where
+    T: BasicInstance,
+    TxDma: usart::TxDma<T>,
+    RxDma: usart::RxDma<T>,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_from_io(buf).await
     }
+}
 
-}
\ No newline at end of file
+impl<'d, T, TxDma, RxDma> embedded_io_async::Write for SerialInterface<'d, T, TxDma, RxDma>
+where
+    T: BasicInstance,
+    TxDma: usart::TxDma<T>,
+    RxDma: usart::RxDma<T>,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_to_io(buf).await?;
+        Ok(buf.len())
+    }
+}
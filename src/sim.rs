@@ -0,0 +1,595 @@
+//! LD06/LD19 模拟器
+//!
+//! 按给定的轨迹在二维场景中移动雷达,对每个测量点做射线求交,输出与真实雷达逐字节一致的
+//! 0x54数据包。[`Simulator`] 本身实现了 `embedded_io::Read`,可以直接作为
+//! [`crate::reader::LidarReader`] 的数据源,也可以用 [`Simulator::write_frames`]
+//! 写入任意 `embedded_io` 输出,或在主机上写入伪终端,在没有硬件时端到端地测试解析、
+//! 拼接和滤波。
+//!
+//! 可以模拟转速抖动、与反射率和入射角相关的强度、随机丢点、测距噪声和近距离的杂散点
+
+use core::convert::Infallible;
+
+use heapless::Vec;
+
+use crate::angle;
+use crate::model::LidarModel;
+use crate::protocol::ld06::{self, PACKET_LEN, POINT_PER_PACK};
+use crate::protocol::Protocol;
+use crate::timing::Pose2;
+
+/// 二维点(m)
+pub type Point2 = (f32, f32);
+
+/// 障碍物形状
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Shape {
+    /// 线段
+    Segment(Point2, Point2),
+    /// 圆
+    Circle {
+        /// 圆心
+        center: Point2,
+        /// 半径(m)
+        radius: f32,
+    },
+}
+
+/// 障碍物
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Obstacle {
+    /// 形状
+    pub shape: Shape,
+    /// 反射率,取值 `[0, 1]`
+    pub reflectivity: f32,
+}
+
+/// 射线与障碍物的交点
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hit {
+    /// 距离(m)
+    pub distance: f32,
+    /// 障碍物的反射率
+    pub reflectivity: f32,
+    /// 入射角的余弦,垂直入射时为1
+    pub cos_incidence: f32,
+}
+
+/// 模拟器错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimError {
+    /// 型号不使用0x54协议
+    Protocol,
+    /// 转速为0或抖动不小于转速
+    Speed,
+    /// 概率或反射率不在 `[0, 1]` 范围内
+    Probability,
+    /// 场景中的障碍物超过容量
+    TooManyObstacles,
+}
+
+/// 最多 `N` 个障碍物组成的二维场景
+#[derive(Debug, Clone, Default)]
+pub struct Scene<const N: usize> {
+    /// 障碍物
+    obstacles: Vec<Obstacle, N>,
+}
+
+impl<const N: usize> Scene<N> {
+    /// 创建空场景
+    pub const fn new() -> Self {
+        Self {
+            obstacles: Vec::new(),
+        }
+    }
+
+    /// 添加障碍物
+    ///
+    /// # Errors
+    /// * 反射率无效或障碍物超过容量时返回 [`SimError`]
+    pub fn add(&mut self, shape: Shape, reflectivity: f32) -> Result<(), SimError> {
+        if !(0.0..=1.0).contains(&reflectivity) {
+            return Err(SimError::Probability);
+        }
+        self.obstacles
+            .push(Obstacle {
+                shape,
+                reflectivity,
+            })
+            .map_err(|_| SimError::TooManyObstacles)
+    }
+
+    /// 添加以原点为中心、宽 `width` 高 `height` 的矩形房间的四面墙
+    pub fn add_room(&mut self, width: f32, height: f32, reflectivity: f32) -> Result<(), SimError> {
+        let (x, y) = (width / 2.0, height / 2.0);
+        let corners = [(x, y), (-x, y), (-x, -y), (x, -y)];
+        for i in 0..4 {
+            self.add(
+                Shape::Segment(corners[i], corners[(i + 1) % 4]),
+                reflectivity,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 所有障碍物
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// 求从 `origin` 沿单位向量 `direction` 出发的射线与场景的最近交点
+    pub fn ray_cast(&self, origin: Point2, direction: Point2) -> Option<Hit> {
+        self.obstacles
+            .iter()
+            .filter_map(|obstacle| {
+                let (distance, cos_incidence) = match obstacle.shape {
+                    Shape::Segment(a, b) => intersect_segment(origin, direction, a, b)?,
+                    Shape::Circle { center, radius } => {
+                        intersect_circle(origin, direction, center, radius)?
+                    }
+                };
+                Some(Hit {
+                    distance,
+                    reflectivity: obstacle.reflectivity,
+                    cos_incidence,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+/// 雷达的运动轨迹
+///
+/// 静止时直接使用 [`Pose2`],闭包 `FnMut(timestamp) -> Pose2` 也实现了该trait
+pub trait Trajectory {
+    /// 雷达在 `timestamp`(微秒)时刻在场景中的位姿
+    fn pose(&mut self, timestamp: u64) -> Pose2;
+}
+
+impl Trajectory for Pose2 {
+    fn pose(&mut self, _timestamp: u64) -> Pose2 {
+        *self
+    }
+}
+
+impl<F: FnMut(u64) -> Pose2> Trajectory for F {
+    fn pose(&mut self, timestamp: u64) -> Pose2 {
+        self(timestamp)
+    }
+}
+
+/// 模拟器配置
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SimConfig {
+    /// 雷达型号,决定采样率和量程
+    pub model: LidarModel,
+    /// 标称转速(度/秒)
+    pub speed: u16,
+    /// 转速在标称值附近随机游走的最大偏差(度/秒)
+    pub speed_jitter: u16,
+    /// 测距噪声的最大值(mm),均匀分布
+    pub range_noise: u16,
+    /// 每个点丢失回波的概率
+    pub dropout: f32,
+    /// 每个点变为近距离杂散点的概率
+    pub near_noise: f32,
+    /// 近距离杂散点的最大距离(mm)
+    pub near_range: u16,
+    /// 随机数种子
+    pub seed: u32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self::with_model(LidarModel::Ld06)
+    }
+}
+
+impl SimConfig {
+    /// 指定型号的典型配置,带有少量噪声
+    pub fn with_model(model: LidarModel) -> Self {
+        Self {
+            model,
+            speed: model.scan_freq() * 360,
+            speed_jitter: 20,
+            range_noise: 10,
+            dropout: 0.01,
+            near_noise: 0.005,
+            near_range: 300,
+            seed: 1,
+        }
+    }
+
+    /// 指定型号的理想配置,没有任何噪声
+    pub fn ideal(model: LidarModel) -> Self {
+        Self {
+            speed_jitter: 0,
+            range_noise: 0,
+            dropout: 0.0,
+            near_noise: 0.0,
+            ..Self::with_model(model)
+        }
+    }
+
+    /// 检查参数是否合理
+    pub fn validate(&self) -> Result<(), SimError> {
+        if self.model.protocol() != Protocol::Ld06 {
+            return Err(SimError::Protocol);
+        }
+        if self.speed == 0 || self.speed_jitter >= self.speed {
+            return Err(SimError::Speed);
+        }
+        if !(0.0..=1.0).contains(&self.dropout) || !(0.0..=1.0).contains(&self.near_noise) {
+            return Err(SimError::Probability);
+        }
+        Ok(())
+    }
+}
+
+/// 雷达模拟器
+pub struct Simulator<T, const N: usize> {
+    /// 场景
+    scene: Scene<N>,
+    /// 运动轨迹
+    trajectory: T,
+    /// 配置参数
+    config: SimConfig,
+    /// 随机数发生器
+    rng: Rng,
+    /// 已经采样的点数
+    samples: u64,
+    /// 下一个点的角度(百万分之一度)
+    angle: u64,
+    /// 当前转速(度/秒)
+    speed: u16,
+    /// 正在输出的数据包
+    frame: [u8; PACKET_LEN],
+    /// 数据包中下一个要输出的字节
+    pos: usize,
+}
+
+impl<T: Trajectory, const N: usize> Simulator<T, N> {
+    /// 创建新的模拟器,从0时刻、0度开始
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`SimError`]
+    pub fn new(scene: Scene<N>, trajectory: T, config: SimConfig) -> Result<Self, SimError> {
+        config.validate()?;
+        Ok(Self {
+            scene,
+            trajectory,
+            rng: Rng::new(config.seed),
+            samples: 0,
+            angle: 0,
+            speed: config.speed,
+            config,
+            frame: [0; PACKET_LEN],
+            pos: PACKET_LEN,
+        })
+    }
+
+    /// 下一个点的采样时刻(微秒)
+    pub fn time(&self) -> u64 {
+        self.sample_time(self.samples)
+    }
+
+    /// 生成下一个数据包
+    pub fn next_frame(&mut self) -> [u8; PACKET_LEN] {
+        let jitter = self.config.speed_jitter as i32;
+        if jitter > 0 {
+            let nominal = self.config.speed as i32;
+            let step = self.rng.signed(jitter / 4 + 1);
+            self.speed =
+                (self.speed as i32 + step).clamp(nominal - jitter, nominal + jitter) as u16;
+        }
+
+        let sample_rate = self.config.model.sample_rate() as u64;
+        let angle_step = self.speed as u64 * 1_000_000 / sample_rate;
+        let start = self.angle;
+        let mut points = [(0u16, 0u8); POINT_PER_PACK];
+        for (i, point) in points.iter_mut().enumerate() {
+            let angle = (start + i as u64 * angle_step) % 360_000_000;
+            let time = self.sample_time(self.samples + i as u64);
+            *point = self.measure(angle, time);
+        }
+
+        let last = POINT_PER_PACK as u64 - 1;
+        let end = (start + last * angle_step) % 360_000_000;
+        // 时间戳对应最后一个点的采样时刻
+        let timestamp = (self.sample_time(self.samples + last) / 1000 % 30000) as u16;
+        self.angle = (start + POINT_PER_PACK as u64 * angle_step) % 360_000_000;
+        self.samples += POINT_PER_PACK as u64;
+
+        ld06::encode(
+            self.speed,
+            (start / 10_000) as u16,
+            (end / 10_000) as u16,
+            timestamp,
+            &points,
+        )
+    }
+
+    /// 向 `writer` 写入 `count` 个数据包
+    pub fn write_frames<W: embedded_io::Write>(
+        &mut self,
+        writer: &mut W,
+        count: usize,
+    ) -> Result<(), W::Error> {
+        for _ in 0..count {
+            writer.write_all(&self.next_frame())?;
+        }
+        Ok(())
+    }
+
+    /// 第 `index` 个点的采样时刻(微秒)
+    fn sample_time(&self, index: u64) -> u64 {
+        index * 1_000_000 / self.config.model.sample_rate() as u64
+    }
+
+    /// 模拟一个点的测量结果
+    ///
+    /// # Arguments
+    /// * `angle` - 角度(百万分之一度)
+    /// * `timestamp` - 采样时刻(微秒)
+    fn measure(&mut self, angle: u64, timestamp: u64) -> (u16, u8) {
+        let model = self.config.model;
+        if self.rng.chance(self.config.near_noise) {
+            let near_range = self.config.near_range.max(model.min_range());
+            let distance = self.rng.range(model.min_range() as u32, near_range as u32);
+            return (distance as u16, self.rng.range(30, 130) as u8);
+        }
+        if self.rng.chance(self.config.dropout) {
+            return (0, 0);
+        }
+
+        let pose = self.trajectory.pose(timestamp);
+        let bearing = pose.theta + (angle as f32 / 1_000_000.0).to_radians();
+        let (sin, cos) = angle::sin_cos_radians(bearing);
+        let Some(hit) = self.scene.ray_cast((pose.x, pose.y), (cos, sin)) else {
+            return (0, 0);
+        };
+
+        let noise = self.config.range_noise as i32;
+        let distance = (hit.distance * 1000.0 + 0.5) as i32 + self.rng.signed(noise);
+        if distance < model.min_range() as i32 || distance > model.max_range() as i32 {
+            return (0, 0);
+        }
+        let falloff = 1.0 - 0.5 * distance as f32 / model.max_range() as f32;
+        let intensity = 255.0 * hit.reflectivity * (0.5 + 0.5 * hit.cos_incidence) * falloff;
+        (distance as u16, intensity.clamp(0.0, 255.0) as u8)
+    }
+}
+
+impl<T, const N: usize> embedded_io::ErrorType for Simulator<T, N> {
+    type Error = Infallible;
+}
+
+/// 源源不断地输出数据包
+impl<T: Trajectory, const N: usize> embedded_io::Read for Simulator<T, N> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.pos == PACKET_LEN {
+            self.frame = self.next_frame();
+            self.pos = 0;
+        }
+        let len = buf.len().min(PACKET_LEN - self.pos);
+        buf[..len].copy_from_slice(&self.frame[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Trajectory, const N: usize> std::io::Read for Simulator<T, N> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match embedded_io::Read::read(self, buf) {
+            Ok(len) => Ok(len),
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// 射线与线段求交,返回距离和入射角余弦
+fn intersect_segment(origin: Point2, d: Point2, a: Point2, b: Point2) -> Option<(f32, f32)> {
+    let e = (b.0 - a.0, b.1 - a.1);
+    let denominator = d.0 * e.1 - d.1 * e.0;
+    if abs(denominator) < 1e-9 {
+        return None;
+    }
+    let w = (a.0 - origin.0, a.1 - origin.1);
+    let t = (w.0 * e.1 - w.1 * e.0) / denominator;
+    let s = (w.0 * d.1 - w.1 * d.0) / denominator;
+    if t <= 0.0 || !(0.0..=1.0).contains(&s) {
+        return None;
+    }
+    Some((t, abs(denominator) / sqrt(e.0 * e.0 + e.1 * e.1)))
+}
+
+/// 射线与圆求交,起点在圆内时得到圆的内壁,返回距离和入射角余弦
+fn intersect_circle(origin: Point2, d: Point2, center: Point2, radius: f32) -> Option<(f32, f32)> {
+    let oc = (origin.0 - center.0, origin.1 - center.1);
+    let b = oc.0 * d.0 + oc.1 * d.1;
+    let c = oc.0 * oc.0 + oc.1 * oc.1 - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = sqrt(discriminant);
+    let t = if -b - root > 0.0 {
+        -b - root
+    } else {
+        -b + root
+    };
+    if t <= 0.0 {
+        return None;
+    }
+    let normal = ((oc.0 + t * d.0) / radius, (oc.1 + t * d.1) / radius);
+    Some((t, abs(normal.0 * d.0 + normal.1 * d.1)))
+}
+
+/// 绝对值,`f32::abs` 在core中不可用
+fn abs(x: f32) -> f32 {
+    if x < 0.0 {
+        -x
+    } else {
+        x
+    }
+}
+
+/// 平方根,用牛顿迭代计算,不依赖libm
+fn sqrt(x: f32) -> f32 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let mut y = f32::from_bits((x.to_bits() >> 1) + 0x1fbd_1df5);
+    for _ in 0..4 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+/// xorshift32随机数发生器
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// 以概率 `p` 返回true
+    fn chance(&mut self, p: f32) -> bool {
+        p > 0.0 && ((self.next() >> 8) as f32 / (1 << 24) as f32) < p
+    }
+
+    /// `[low, high]` 内的均匀分布
+    fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + self.next() % (high - low + 1)
+    }
+
+    /// `[-max, max]` 内的均匀分布
+    fn signed(&mut self, max: i32) -> i32 {
+        if max <= 0 {
+            return 0;
+        }
+        self.range(0, 2 * max as u32) as i32 - max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::slbf::Slbf;
+    use crate::reader::LidarReader;
+    use crate::scan::{ScanAssembler, ScanEvent};
+
+    #[test]
+    fn test_ideal_round_room() {
+        let mut scene = Scene::<1>::new();
+        scene
+            .add(
+                Shape::Circle {
+                    center: (0.0, 0.0),
+                    radius: 2.0,
+                },
+                0.9,
+            )
+            .unwrap();
+        let sim = Simulator::new(scene, Pose2::default(), SimConfig::ideal(LidarModel::Ld06));
+        let mut reader = LidarReader::with_model(sim.unwrap(), LidarModel::Ld06);
+
+        let mut last_timestamp = 0;
+        for i in 0..200 {
+            let packet = reader.read_packet_blocking().unwrap();
+            assert_eq!(packet.speed, 3600);
+            assert_eq!(
+                angle::to_centidegrees(packet.start_angle),
+                (i * 960 % 36000) as u16
+            );
+            assert!(packet.points.iter().all(|p| p.distance.abs_diff(2000) <= 1));
+            assert!(packet.points.iter().all(|p| p.intensity > 200));
+            assert!(packet.timestamp >= last_timestamp);
+            last_timestamp = packet.timestamp;
+        }
+    }
+
+    #[test]
+    fn test_wall_distance_and_motion() {
+        let mut scene = Scene::<4>::new();
+        scene.add_room(4.0, 4.0, 0.9).unwrap();
+        // 以0.5m/s沿x轴移动,0度方向的墙逐渐靠近
+        let trajectory = |t: u64| Pose2 {
+            x: t as f32 / 2_000_000.0,
+            ..Pose2::default()
+        };
+        let mut sim =
+            Simulator::new(scene, trajectory, SimConfig::ideal(LidarModel::Ld06)).unwrap();
+
+        let mut frames = std::vec::Vec::new();
+        sim.write_frames(&mut frames, 375).unwrap();
+        assert_eq!(frames.len(), 375 * PACKET_LEN);
+        assert_eq!(sim.time(), 1_000_000);
+
+        let mut reader = LidarReader::with_model(frames.as_slice(), LidarModel::Ld06);
+        let first = reader.read_packet_blocking().unwrap();
+        assert_eq!(first.points[0].distance, 2000);
+        let last = core::iter::from_fn(|| reader.read_packet_blocking().ok())
+            .filter(|p| angle::to_centidegrees(p.start_angle) == 0)
+            .last()
+            .unwrap();
+        // 第300包从0度开始,此时已向前移动0.4m
+        assert!(last.points[0].distance.abs_diff(1600) <= 2);
+    }
+
+    #[test]
+    fn test_end_to_end_near_filter() {
+        let mut scene = Scene::<8>::new();
+        scene.add_room(4.0, 6.0, 0.8).unwrap();
+        scene
+            .add(
+                Shape::Circle {
+                    center: (1.2, 0.5),
+                    radius: 0.1,
+                },
+                0.5,
+            )
+            .unwrap();
+        let config = SimConfig {
+            near_noise: 0.05,
+            seed: 7,
+            ..SimConfig::with_model(LidarModel::Ld06)
+        };
+        let sim = Simulator::new(scene, Pose2::default(), config).unwrap();
+        let mut reader = LidarReader::with_model(sim, LidarModel::Ld06);
+        let mut assembler = ScanAssembler::<512>::with_model(LidarModel::Ld06);
+        let mut filter = Slbf::new(3600.0, false);
+        filter.set_config(LidarModel::Ld06.slbf_config()).unwrap();
+
+        let mut scans = 0;
+        while scans < 5 {
+            let packet = reader.read_packet_blocking().unwrap();
+            for point in packet.points {
+                if let Some(ScanEvent::Complete(scan)) = assembler.push(point, packet.speed) {
+                    let near = |p: &crate::PointData| p.distance != 0 && p.distance < 1000;
+                    assert!(scan.points.iter().filter(|p| near(p)).count() > 5);
+                    filter.near_filter_in_place(&mut scan.points);
+                    assert!(!scan.points.iter().any(near));
+                    assert!(scan.len() > 350);
+                    scans += 1;
+                }
+            }
+        }
+    }
+}
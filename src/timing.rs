@@ -0,0 +1,280 @@
+//! 时间戳与运动畸变补偿
+//!
+//! [`PointData::timestamp`] 统一使用MCU(或主机)时钟的微秒数。[`Timestamper`]
+//! 用 [`ClockSync`] 把数据包的设备时间戳换算到MCU时钟,再按转速为每个点插值出采样时刻;
+//! [`deskew`] 根据运动估计补偿一圈扫描期间雷达移动造成的点云畸变
+
+use heapless::Vec;
+
+use crate::angle;
+use crate::clock_sync::{ClockSync, ClockSyncConfig, ClockSyncConfigError};
+use crate::filter::slbf::PointData;
+use crate::model::LidarModel;
+use crate::protocol::{ld06, ld14, Packet, Protocol};
+
+/// 为数据包中的每个点填写采样时刻
+///
+/// 数据包的时刻取最后一个点的采样时刻:有设备时间戳的协议经 [`ClockSync`]
+/// 换算,否则用接收时刻减去数据包的传输时间。其余各点按转速和角度差向前推算
+pub struct Timestamper {
+    /// 数据协议
+    protocol: Protocol,
+    /// 串口波特率
+    baud_rate: u32,
+    /// 除传输时间外的固定延迟(微秒)
+    latency: u64,
+    /// 设备时钟同步
+    clock: ClockSync,
+}
+
+impl Default for Timestamper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timestamper {
+    /// 创建新的实例,使用默认型号
+    pub const fn new() -> Self {
+        Self::with_model(LidarModel::DEFAULT)
+    }
+
+    /// 创建指定型号的实例
+    pub const fn with_model(model: LidarModel) -> Self {
+        Self {
+            protocol: model.protocol(),
+            baud_rate: model.baud_rate(),
+            latency: 0,
+            clock: ClockSync::new(),
+        }
+    }
+
+    /// 设置除传输时间外的固定延迟(微秒),例如串口空闲中断的等待时间
+    pub fn set_latency(&mut self, latency: u64) {
+        self.latency = latency;
+    }
+
+    /// 设置设备时钟同步的参数,同时丢弃历史数据
+    ///
+    /// # Errors
+    /// * 配置参数不合理时返回 [`ClockSyncConfigError`]
+    pub fn set_clock_config(
+        &mut self,
+        config: ClockSyncConfig,
+    ) -> Result<(), ClockSyncConfigError> {
+        self.clock = ClockSync::with_config(config)?;
+        Ok(())
+    }
+
+    /// 设备时钟同步
+    pub fn clock(&self) -> &ClockSync {
+        &self.clock
+    }
+
+    /// 丢弃设备时钟同步的历史数据
+    pub fn reset(&mut self) {
+        self.clock.reset();
+    }
+
+    /// 为数据包的每个点填写时间戳
+    ///
+    /// # Arguments
+    /// * `packet` - 解析出的数据包
+    /// * `received` - 数据包接收完成时的MCU时刻(微秒)
+    pub fn stamp(&mut self, packet: &mut Packet, received: u64) {
+        let Some(last) = packet.points.last() else {
+            return;
+        };
+        let last_angle = angle::to_centidegrees(last.angle) as u64;
+
+        let frame_len = match self.protocol {
+            Protocol::Ld06 => ld06::PACKET_LEN,
+            Protocol::Ld14 => ld14::frame_len(packet.points.len()),
+        };
+        let transmit = frame_len as u64 * 10_000_000 / self.baud_rate as u64;
+        let end = received.saturating_sub(transmit + self.latency);
+        let end = match self.protocol {
+            Protocol::Ld06 => self.clock.update(packet.timestamp, end),
+            Protocol::Ld14 => end,
+        };
+
+        for point in packet.points.iter_mut() {
+            // 角度差(0.01度) / 转速(度/秒) 换算为微秒
+            let diff = (last_angle + 36000 - angle::to_centidegrees(point.angle) as u64) % 36000;
+            let before = diff * 10_000 / (packet.speed as u64).max(1);
+            point.timestamp = end.saturating_sub(before);
+        }
+    }
+}
+
+/// 二维位姿
+///
+/// 坐标系的x轴指向0度,y轴指向90度,与 [`crate::angle`] 的角度方向一致
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Pose2 {
+    /// x坐标(m)
+    pub x: f32,
+    /// y坐标(m)
+    pub y: f32,
+    /// 朝向(弧度),从x轴转向y轴为正
+    pub theta: f32,
+}
+
+/// 运动估计
+///
+/// 闭包 `FnMut(timestamp, reference) -> Pose2` 也实现了该trait,可用于接入里程计
+pub trait Motion {
+    /// 雷达在 `timestamp` 时刻的位姿,在 `reference` 时刻的雷达坐标系下表示
+    fn pose(&mut self, timestamp: u64, reference: u64) -> Pose2;
+}
+
+impl<F: FnMut(u64, u64) -> Pose2> Motion for F {
+    fn pose(&mut self, timestamp: u64, reference: u64) -> Pose2 {
+        self(timestamp, reference)
+    }
+}
+
+/// 匀速运动,速度在雷达坐标系下表示
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConstantVelocity {
+    /// x方向速度(m/s)
+    pub vx: f32,
+    /// y方向速度(m/s)
+    pub vy: f32,
+    /// 角速度(弧度/秒)
+    pub omega: f32,
+}
+
+impl Motion for ConstantVelocity {
+    fn pose(&mut self, timestamp: u64, reference: u64) -> Pose2 {
+        let dt = (timestamp as i64 - reference as i64) as f32 / 1_000_000.0;
+        Pose2 {
+            x: self.vx * dt,
+            y: self.vy * dt,
+            theta: self.omega * dt,
+        }
+    }
+}
+
+/// 直角坐标表示的测量点
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CartesianPoint {
+    /// x坐标(m)
+    pub x: f32,
+    /// y坐标(m)
+    pub y: f32,
+    /// 强度
+    pub intensity: u8,
+    /// 采样时刻
+    pub timestamp: u64,
+}
+
+/// 补偿运动畸变,把每个点变换到 `reference` 时刻的雷达坐标系下
+///
+/// 距离为0的点被忽略,`out` 容量不足时多余的点被丢弃
+///
+/// # Arguments
+/// * `points` - 已填写时间戳的点云
+/// * `reference` - 参考时刻,通常取一圈的结束时刻
+/// * `motion` - 运动估计,静止时使用 `ConstantVelocity::default()`
+/// * `out` - 输出的点云
+pub fn deskew<M: Motion + ?Sized, const N: usize>(
+    points: &[PointData],
+    reference: u64,
+    motion: &mut M,
+    out: &mut Vec<CartesianPoint, N>,
+) {
+    out.clear();
+    for point in points.iter().filter(|p| p.distance != 0) {
+        let pose = motion.pose(point.timestamp, reference);
+        let (sin, cos) = angle::sin_cos(point.angle);
+        let r = point.distance as f32 / 1000.0;
+        let (x, y) = (r * cos, r * sin);
+
+        let (sin, cos) = angle::sin_cos_radians(pose.theta);
+        let point = CartesianPoint {
+            x: cos * x - sin * y + pose.x,
+            y: sin * x + cos * y + pose.y,
+            intensity: point.intensity,
+            timestamp: point.timestamp,
+        };
+        if out.push(point).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_points() {
+        let mut packet = Packet {
+            speed: 3600,
+            start_angle: angle::from_degrees(100.0),
+            end_angle: angle::from_degrees(108.8),
+            timestamp: 1000,
+            points: (0..12u16)
+                .map(|i| PointData {
+                    angle: angle::from_centidegrees(10000 + i * 80),
+                    distance: 1000,
+                    intensity: 200,
+                    timestamp: 0,
+                })
+                .collect(),
+        };
+        let mut stamper = Timestamper::with_model(LidarModel::Ld06);
+        stamper.stamp(&mut packet, 5_000_000);
+
+        // 47字节在230400波特率下的传输时间为2039us,相邻两点间隔0.8度即222us
+        assert_eq!(packet.points[11].timestamp, 5_000_000 - 2039);
+        assert_eq!(packet.points[0].timestamp, 5_000_000 - 2039 - 2444);
+        assert!(packet
+            .points
+            .windows(2)
+            .all(|w| w[0].timestamp < w[1].timestamp));
+    }
+
+    #[test]
+    fn test_deskew_constant_velocity() {
+        // 以1m/s向前移动时扫描正前方2m处与x轴垂直的墙,
+        // 参考时刻为最后一个点的采样时刻,此前每1ms采样一个点
+        let points: std::vec::Vec<PointData> = (0..41u16)
+            .map(|i| {
+                let degrees = i as f32 - 20.0;
+                let timestamp = 1_000_000 + i as u64 * 1000;
+                let x = -((40 - i) as f32) * 0.001;
+                let distance = (2.0 - x) / angle::sin_cos_radians(degrees.to_radians()).1;
+                PointData {
+                    angle: angle::from_degrees((degrees + 360.0) % 360.0),
+                    distance: (distance * 1000.0 + 0.5) as u16,
+                    intensity: 200,
+                    timestamp,
+                }
+            })
+            .collect();
+        let reference = points[40].timestamp;
+
+        let mut motion = ConstantVelocity {
+            vx: 1.0,
+            ..ConstantVelocity::default()
+        };
+        let mut out = Vec::<CartesianPoint, 64>::new();
+        deskew(&points, reference, &mut motion, &mut out);
+        assert_eq!(out.len(), 41);
+        assert!(out.iter().all(|p| (p.x - 2.0).abs() < 0.002));
+
+        // 里程计以闭包形式给出时结果相同
+        let mut odometry = |timestamp: u64, reference: u64| Pose2 {
+            x: (timestamp as f32 - reference as f32) / 1_000_000.0,
+            ..Pose2::default()
+        };
+        let mut from_odometry = Vec::<CartesianPoint, 64>::new();
+        deskew(&points, reference, &mut odometry, &mut from_odometry);
+        assert_eq!(out, from_odometry);
+    }
+}
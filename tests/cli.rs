@@ -0,0 +1,180 @@
+//! 命令行工具端到端测试
+//!
+//! 用模拟器生成LD06录制文件,运行各子命令并检查输出
+
+#![cfg(all(feature = "cli", target_os = "linux"))]
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use ldlidar_driver::capture::{CaptureHeader, CaptureWriter, StdWriter};
+use ldlidar_driver::sim::{Scene, Shape, SimConfig, Simulator};
+use ldlidar_driver::timing::Pose2;
+use ldlidar_driver::LidarModel;
+
+/// 4圈的数据包数
+const FRAMES: usize = 150;
+
+/// 测试用的临时目录,每个测试使用自己的目录
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("ldlidar-cli-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// 半径1.5m的圆形房间中静止的LD06连续4圈的数据包
+///
+/// 每 `corrupt` 个数据包破坏一个的CRC,为0时不破坏
+fn write_capture(path: &Path, corrupt: usize) -> Vec<u8> {
+    let mut scene = Scene::<1>::new();
+    let wall = Shape::Circle {
+        center: (0.0, 0.0),
+        radius: 1.5,
+    };
+    scene.add(wall, 0.9).unwrap();
+    let config = SimConfig::ideal(LidarModel::Ld06);
+    let mut sim = Simulator::new(scene, Pose2::default(), config).unwrap();
+
+    let header = CaptureHeader::new(LidarModel::Ld06, "1.0.0");
+    let mut writer = CaptureWriter::new(StdWriter(File::create(path).unwrap()), header);
+    let mut raw = Vec::new();
+    for i in 0..FRAMES {
+        let time = sim.time();
+        let mut frame = sim.next_frame();
+        if corrupt > 0 && i % corrupt == corrupt - 1 {
+            *frame.last_mut().unwrap() ^= 0xFF;
+        }
+        writer.record_blocking(time, &frame).unwrap();
+        raw.extend_from_slice(&frame);
+    }
+    writer.flush_blocking().unwrap();
+    raw
+}
+
+/// 运行命令行工具,返回标准输出
+fn ldlidar(args: &[&Path]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ldlidar"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "ldlidar {args:?}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// `stats` 输出中某一项的值
+fn stat<'a>(stats: &'a str, name: &str) -> &'a str {
+    stats
+        .lines()
+        .find_map(|line| line.strip_prefix(name))
+        .unwrap_or_else(|| panic!("no {name} in {stats}"))
+        .trim()
+}
+
+#[test]
+fn test_dump_and_stats() {
+    let dir = WorkDir::new("stats");
+    let capture = dir.path("field.cap");
+    write_capture(&capture, 10);
+
+    let dump = ldlidar(&[
+        Path::new("dump"),
+        &capture,
+        Path::new("--format"),
+        Path::new("json"),
+    ]);
+    let packets = dump
+        .lines()
+        .filter(|line| line.starts_with("{\"timestamp\""))
+        .count();
+    assert_eq!(packets, FRAMES - FRAMES / 10);
+    assert_eq!(dump.lines().count() - packets, FRAMES / 10);
+
+    let stats = ldlidar(&[Path::new("stats"), &capture]);
+    assert_eq!(stat(&stats, "model"), "LD06");
+    assert_eq!(stat(&stats, "packets"), "135");
+    assert_eq!(stat(&stats, "crc errors"), "15 (10.000%)");
+    assert_eq!(stat(&stats, "other errors"), "0 (0.000%)");
+}
+
+#[test]
+fn test_record_and_replay() {
+    let dir = WorkDir::new("record");
+    let raw = write_capture(&dir.path("field.cap"), 0);
+    let input = dir.path("field.bin");
+    fs::write(&input, &raw).unwrap();
+
+    // 录制原始字节文件,再按100倍速回放,得到相同的字节
+    let recorded = dir.path("recorded.cap");
+    ldlidar(&[Path::new("record"), &input, &recorded]);
+    let stats = ldlidar(&[Path::new("stats"), &recorded]);
+    assert_eq!(stat(&stats, "packets"), &FRAMES.to_string());
+
+    let replayed = dir.path("replayed.bin");
+    ldlidar(&[
+        Path::new("replay"),
+        &recorded,
+        Path::new("--speed"),
+        Path::new("100"),
+        Path::new("--output"),
+        &replayed,
+    ]);
+    assert_eq!(fs::read(&replayed).unwrap(), raw);
+}
+
+#[test]
+fn test_filter_and_convert() {
+    let dir = WorkDir::new("convert");
+    let capture = dir.path("field.cap");
+    let raw = write_capture(&capture, 0);
+
+    // 第一圈从中途开始,最后一圈没有回到0度,中间两圈完整
+    let csv = dir.path("field.csv");
+    ldlidar(&[Path::new("filter"), &capture, Path::new("--output"), &csv]);
+    let csv = fs::read_to_string(&csv).unwrap();
+    assert_eq!(csv.lines().count(), 1 + 2 * 450);
+    assert!(csv
+        .lines()
+        .skip(1)
+        .all(|line| line.split(',').nth(3) == Some("1500")));
+
+    let pcd = dir.path("field.pcd");
+    ldlidar(&[Path::new("convert"), &capture, &pcd]);
+    let pcd = fs::read_to_string(&pcd).unwrap();
+    assert!(pcd.contains("POINTS 900\n"));
+    assert_eq!(pcd.lines().count(), 11 + 900);
+
+    let bin = dir.path("field.bin");
+    ldlidar(&[Path::new("convert"), &capture, &bin]);
+    assert_eq!(fs::read(&bin).unwrap(), raw);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ldlidar"))
+        .args([
+            Path::new("filter"),
+            &capture,
+            Path::new("--format"),
+            Path::new("text"),
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,148 @@
+//! 主机端驱动测试
+//!
+//! 通过伪终端向驱动发送模拟器生成的LD06数据包,检查阻塞、异步和回调三种接收方式
+
+#![cfg(all(feature = "std", target_os = "linux"))]
+
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ldlidar_driver::capture::{
+    CaptureHeader, CaptureWriter, Replay, ReplayMode, StdClock, StdWriter, Tap,
+};
+use ldlidar_driver::host::driver::STOP_TIMEOUT;
+use ldlidar_driver::host::serial::open_pty;
+use ldlidar_driver::host::{DriverConfig, DriverError, LidarDriver};
+use ldlidar_driver::reader::StdReader;
+use ldlidar_driver::sim::{Scene, Shape, SimConfig, Simulator};
+use ldlidar_driver::timing::Pose2;
+use ldlidar_driver::LidarModel;
+
+/// 半径1.5m的圆形房间中静止的LD06连续 `revolutions` 圈的数据包
+fn frames(revolutions: usize) -> Vec<u8> {
+    let mut scene = Scene::<1>::new();
+    let wall = Shape::Circle {
+        center: (0.0, 0.0),
+        radius: 1.5,
+    };
+    scene.add(wall, 0.9).unwrap();
+    let config = SimConfig::ideal(LidarModel::Ld06);
+    let mut sim = Simulator::new(scene, Pose2::default(), config).unwrap();
+    let mut bytes = Vec::new();
+    sim.write_frames(&mut bytes, revolutions * 375 / 10)
+        .unwrap();
+    bytes
+}
+
+fn config() -> DriverConfig {
+    DriverConfig {
+        filter: false,
+        ..DriverConfig::with_model(LidarModel::Ld06)
+    }
+}
+
+#[test]
+fn test_blocking_over_pty() {
+    let (mut master, path) = open_pty().unwrap();
+    let driver = LidarDriver::open(&path, config()).unwrap();
+
+    let writer = thread::spawn(move || {
+        master.write_all(&frames(4)).unwrap();
+        master
+    });
+    let scan = driver.get_laser_scan_data(Duration::from_secs(5)).unwrap();
+    assert_eq!(scan.len(), 450);
+    assert!(scan.start_timestamp < scan.end_timestamp);
+    assert!(scan.points.iter().all(|p| p.distance == 1500));
+
+    let _master = writer.join().unwrap();
+    assert!(driver.is_running());
+}
+
+#[test]
+fn test_async_until_end_of_stream() {
+    let driver = LidarDriver::start(Cursor::new(frames(4)), config()).unwrap();
+    let mut scans = 0;
+    loop {
+        match embassy_futures::block_on(driver.next_scan()) {
+            Ok(scan) => {
+                assert_eq!(scan.len(), 450);
+                scans += 1;
+            }
+            Err(DriverError::Stopped) => break,
+            Err(err) => panic!("{err}"),
+        }
+    }
+    // 只保留未取走的最新一圈,至少能收到一圈
+    assert!(scans >= 1);
+    assert!(!driver.is_running());
+}
+
+#[test]
+fn test_callback_receives_every_scan() {
+    let lengths = Arc::new(Mutex::new(Vec::new()));
+    let sink = lengths.clone();
+    let mut driver = LidarDriver::with_callback(Cursor::new(frames(4)), config(), move |scan| {
+        sink.lock().unwrap().push(scan.len());
+    })
+    .unwrap();
+    assert!(matches!(
+        driver.get_laser_scan_data(Duration::from_secs(5)),
+        Err(DriverError::Stopped)
+    ));
+    driver.stop();
+    // 第一圈从中途开始被丢弃,最后一圈没有回到0度,中间两圈完整
+    assert_eq!(*lengths.lock().unwrap(), [450, 450]);
+}
+
+#[test]
+fn test_drop_with_blocked_reader() {
+    // 套接字的读取没有超时,对端不发送数据时后台线程一直阻塞
+    let (reader, _peer) = UnixStream::pair().unwrap();
+    let driver = LidarDriver::start(reader, config()).unwrap();
+    let started = Instant::now();
+    drop(driver);
+    assert!(started.elapsed() < STOP_TIMEOUT * 2);
+}
+
+#[test]
+fn test_record_then_replay() {
+    let path = std::env::temp_dir().join(format!("ldlidar-{}.cap", std::process::id()));
+    let header = CaptureHeader::new(LidarModel::Ld06, "1.0.0");
+    let writer = CaptureWriter::new(StdWriter(File::create(&path).unwrap()), header);
+    let tap = Tap::new(Cursor::new(frames(4)), writer, StdClock::default());
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let sink = recorded.clone();
+    let mut driver = LidarDriver::with_callback(tap, config(), move |scan| {
+        sink.lock().unwrap().push(scan.len());
+    })
+    .unwrap();
+    assert!(matches!(
+        driver.get_laser_scan_data(Duration::from_secs(5)),
+        Err(DriverError::Stopped)
+    ));
+    driver.stop();
+
+    let file = StdReader(File::open(&path).unwrap());
+    let replay = Replay::new(file, ReplayMode::Accelerated(100), StdClock::default()).unwrap();
+    assert_eq!(replay.header().firmware(), "1.0.0");
+    let replayed = Arc::new(Mutex::new(Vec::new()));
+    let sink = replayed.clone();
+    let mut driver = LidarDriver::with_callback(replay, config(), move |scan| {
+        sink.lock().unwrap().push(scan.len());
+    })
+    .unwrap();
+    assert!(matches!(
+        driver.get_laser_scan_data(Duration::from_secs(5)),
+        Err(DriverError::Stopped)
+    ));
+    driver.stop();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(*recorded.lock().unwrap(), [450, 450]);
+    assert_eq!(*replayed.lock().unwrap(), [450, 450]);
+}
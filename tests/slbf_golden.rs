@@ -0,0 +1,200 @@
+//! Slbf 回归测试
+//!
+//! `tests/data/slbf/` 下每个 `.txt` 文件包含一圈扫描数据和期望的滤波结果:
+//!
+//! ```text
+//! # 注释
+//! sdk <上游 ldlidar_stl_sdk 的提交>
+//! model LD06
+//! scan_freq 4500
+//! speed 4500
+//! strict 1
+//! input
+//! <角度> <距离> <强度>
+//! ...
+//! expected
+//! <角度> <距离> <强度>
+//! ...
+//! ```
+//!
+//! 期望结果只能由上游C++ SDK的Slbf生成(见 `tests/data/slbf/gen_golden.cpp`),不能用本实现的输出重写,
+//! 否则测试只是在和自己比较。生成程序会在 `sdk` 一行写入所用SDK的提交。
+//!
+//! 目前的夹具都是合成场景,期望结果来自本实现,没有 `sdk` 一行,只能防止行为意外改变,
+//! 不能说明与上游一致。[`test_fixtures_from_upstream`] 在换成实测数据和上游结果之前保持忽略。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ldlidar_driver::{angle, LidarModel, PointData, Slbf, SlbfConfig};
+
+/// 期望结果中角度的允许误差
+const ANGLE_TOLERANCE: f32 = 1e-3;
+
+/// 一圈输入的最大点数
+const MAX_POINTS: usize = 4096;
+
+/// 一个回归测试用例
+struct Fixture {
+    /// 生成期望结果的上游SDK提交,期望结果不是来自上游时为 `None`
+    sdk: Option<String>,
+    /// 雷达型号
+    model: LidarModel,
+    /// 测量频率(点/秒),生成期望结果时使用的值,未给出时使用型号的默认配置
+    scan_freq: Option<u16>,
+    /// 转速(度/秒)
+    speed: u32,
+    /// 是否启用严格过滤策略
+    strict: bool,
+    /// 输入点云
+    input: Vec<PointData>,
+    /// 期望输出
+    expected: Vec<PointData>,
+}
+
+fn parse_point(line: &str) -> PointData {
+    let mut fields = line.split_whitespace();
+    let mut next = || {
+        fields
+            .next()
+            .unwrap_or_else(|| panic!("bad point line: {line}"))
+    };
+    PointData {
+        angle: angle::from_degrees(next().parse().unwrap()),
+        distance: next().parse().unwrap(),
+        intensity: next().parse().unwrap(),
+        timestamp: 0,
+    }
+}
+
+fn parse(text: &str) -> Fixture {
+    let mut fixture = Fixture {
+        sdk: None,
+        model: LidarModel::DEFAULT,
+        scan_freq: None,
+        speed: 0,
+        strict: false,
+        input: Vec::new(),
+        expected: Vec::new(),
+    };
+    let mut section = None;
+    let lines = text.lines().map(str::trim);
+    for line in lines.filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        if let Some(sdk) = line.strip_prefix("sdk ") {
+            fixture.sdk = Some(sdk.to_owned());
+        } else if let Some(model) = line.strip_prefix("model ") {
+            fixture.model = model.parse().unwrap();
+        } else if let Some(scan_freq) = line.strip_prefix("scan_freq ") {
+            fixture.scan_freq = Some(scan_freq.parse().unwrap());
+        } else if let Some(speed) = line.strip_prefix("speed ") {
+            fixture.speed = speed.parse().unwrap();
+        } else if let Some(strict) = line.strip_prefix("strict ") {
+            fixture.strict = strict == "1";
+        } else if line == "input" || line == "expected" {
+            section = Some(line == "input");
+        } else {
+            match section {
+                Some(true) => fixture.input.push(parse_point(line)),
+                Some(false) => fixture.expected.push(parse_point(line)),
+                None => panic!("point outside of section: {line}"),
+            }
+        }
+    }
+    fixture
+}
+
+fn same_point(a: &PointData, b: &PointData) -> bool {
+    (angle::to_degrees(a.angle) - angle::to_degrees(b.angle)).abs() < ANGLE_TOLERANCE
+        && a.distance == b.distance
+        && a.intensity == b.intensity
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/slbf");
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// 按给定配置对夹具的输入运行近距离滤波
+fn run(fixture: &Fixture, config: SlbfConfig) -> heapless::Vec<PointData, MAX_POINTS> {
+    let mut filter = Slbf::new(fixture.speed as f32, fixture.strict);
+    filter.set_config(config).unwrap();
+    filter.near_filter(&fixture.input)
+}
+
+#[test]
+fn test_near_filter_golden() {
+    let paths = fixtures();
+    assert!(!paths.is_empty(), "no fixtures in tests/data/slbf");
+
+    let mut failures = Vec::new();
+    for path in paths {
+        let fixture = parse(&fs::read_to_string(&path).unwrap());
+        let mut config = fixture.model.slbf_config();
+        if let Some(scan_freq) = fixture.scan_freq {
+            config.scan_freq = scan_freq;
+        }
+        let output = run(&fixture, config);
+
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        if output.len() != fixture.expected.len() {
+            failures.push(format!(
+                "{name}: expected {} points, got {}",
+                fixture.expected.len(),
+                output.len()
+            ));
+            continue;
+        }
+        if let Some(i) = (0..output.len()).find(|&i| !same_point(&output[i], &fixture.expected[i]))
+        {
+            failures.push(format!(
+                "{name}: point {i} differs, expected {:?}, got {:?}",
+                fixture.expected[i], output[i]
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_stl27l_model_scan_freq() {
+    // STL-27L夹具的期望结果按测量频率2300生成。按型号自身的21600运行时,同组相邻点的
+    // 最大角度差从 3600/2300*2 ≈ 3.13 度缩小到 3600/21600*2 ≈ 0.33 度,
+    // 176.50、177.33、179.50 度三个孤立的低置信度近距离点不再归为一组,各自因点数不足被丢弃
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/slbf/stl27l_sector.txt");
+    let fixture = parse(&fs::read_to_string(path).unwrap());
+    assert_eq!(fixture.scan_freq, Some(2300));
+    let config = fixture.model.slbf_config();
+    assert_eq!(config.scan_freq, 21600);
+
+    let output = run(&fixture, config);
+    let dropped: Vec<_> = fixture
+        .expected
+        .iter()
+        .filter(|point| !output.iter().any(|p| same_point(p, point)))
+        .map(|point| (point.distance, point.intensity))
+        .collect();
+    assert_eq!(dropped, [(331, 117), (744, 98), (329, 97)]);
+    assert_eq!(output.len(), fixture.expected.len() - dropped.len());
+}
+
+#[test]
+#[ignore = "夹具仍是合成场景,期望结果不是由上游SDK生成"]
+fn test_fixtures_from_upstream() {
+    let missing: Vec<_> = fixtures()
+        .into_iter()
+        .filter(|path| parse(&fs::read_to_string(path).unwrap()).sdk.is_none())
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "expected results not generated by the upstream SDK: {}",
+        missing.join(", ")
+    );
+}